@@ -0,0 +1,70 @@
+//! Arithmetic trace generation for 1M operations. Run it with and
+//! without `--features rayon` to compare the sequential and parallel
+//! paths. `trace_builder_1m` pushes the operations one at a time into
+//! a `TraceBuilder`, as a caller streaming them from an instruction
+//! trace would. `uniform_div_64k` generates a trace of nothing but DIV,
+//! which takes two rows per operation.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use zkm::arithmetic::api::{
+    estimated_rows, generate_trace, generate_uniform_trace, ArithOp, BinaryOperator, TraceBuilder,
+};
+
+type F = GoldilocksField;
+const D: usize = 2;
+
+const N_OPS: usize = 1 << 20;
+
+fn random_ops() -> Vec<ArithOp> {
+    let operators = [
+        BinaryOperator::ADDU,
+        BinaryOperator::MULT,
+        BinaryOperator::DIVU,
+        BinaryOperator::SLL,
+        BinaryOperator::SRA,
+        BinaryOperator::SLTU,
+    ];
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+    (0..N_OPS)
+        .map(|i| {
+            let operator = operators[i % operators.len()];
+            ArithOp::binary(operator, rng.gen(), rng.gen::<u32>() & 0x1f)
+        })
+        .collect()
+}
+
+fn bench_generate_trace(c: &mut Criterion) {
+    let mode = if cfg!(feature = "rayon") {
+        "parallel"
+    } else {
+        "sequential"
+    };
+    let ops = random_ops();
+
+    let mut group = c.benchmark_group("arithmetic_trace");
+    group.sample_size(10);
+    group.bench_function(format!("generate_trace_1m_{mode}"), |b| {
+        b.iter_batched(
+            || ops.clone(),
+            generate_trace::<F, D>,
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("trace_builder_1m", |b| {
+        b.iter(|| {
+            let mut builder = TraceBuilder::<F>::with_capacity(estimated_rows(&ops));
+            builder.extend(ops.iter().cloned());
+            builder.finish()
+        })
+    });
+    group.bench_function("uniform_div_64k", |b| {
+        b.iter(|| generate_uniform_trace::<F, D>(BinaryOperator::DIV, 1 << 16))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_generate_trace);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+//! Row generation for SLL and SRL, which look up their powers of two
+//! in `shift::POWERS_OF_TWO`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use zkm::arithmetic::api::{to_trace_rows, ArithOp, BinaryOperator};
+
+type F = GoldilocksField;
+
+const N_OPS: usize = 1 << 12;
+
+fn bench_shift_rows(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+    let mut group = c.benchmark_group("shift_rows");
+    for operator in [BinaryOperator::SLL, BinaryOperator::SRL] {
+        let ops: Vec<ArithOp> = (0..N_OPS)
+            .map(|_| ArithOp::binary(operator, rng.gen(), rng.gen::<u32>() & 0x1f))
+            .collect();
+        group.bench_function(format!("{operator:?}_4k"), |b| {
+            b.iter(|| {
+                for op in &ops {
+                    black_box(to_trace_rows::<F>(op));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_shift_rows);
+criterion_main!(benches);
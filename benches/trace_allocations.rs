@@ -0,0 +1,83 @@
+//! Heap allocations made while generating the arithmetic trace of 64k
+//! operations, counted by a wrapping global allocator. Unlike the
+//! criterion benches this prints counts rather than times, so that
+//! changes to the row generation can be compared by running it before
+//! and after.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use zkm::arithmetic::api::{estimated_rows, generate_trace, ArithOp, BinaryOperator, TraceBuilder};
+
+type F = GoldilocksField;
+const D: usize = 2;
+
+const N_OPS: usize = 1 << 16;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn random_ops() -> Vec<ArithOp> {
+    let operators = [
+        BinaryOperator::ADDU,
+        BinaryOperator::MULT,
+        BinaryOperator::DIVU,
+        BinaryOperator::SLL,
+        BinaryOperator::SRA,
+        BinaryOperator::SLTU,
+    ];
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+    (0..N_OPS)
+        .map(|i| {
+            let operator = operators[i % operators.len()];
+            ArithOp::binary(operator, rng.gen(), rng.gen::<u32>() & 0x1f)
+        })
+        .collect()
+}
+
+/// The number of allocations made by `f`.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(result);
+    after - before
+}
+
+fn main() {
+    let ops = random_ops();
+
+    let inputs = ops.clone();
+    let n = count_allocations(|| generate_trace::<F, D>(inputs));
+    println!("generate_trace: {n} allocations for {N_OPS} operations");
+
+    let n = count_allocations(|| {
+        let mut builder = TraceBuilder::<F>::with_capacity(estimated_rows(&ops));
+        builder.extend(ops.iter().cloned());
+        builder.finish()
+    });
+    println!("trace_builder: {n} allocations for {N_OPS} operations");
+}
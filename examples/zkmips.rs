@@ -105,7 +105,7 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     let helper = || {
         println!(
-            "Help: {} split | prove | aggregate_proof | aggregate_proof_all | prove_groth16",
+            "Help: {} split | prove | aggregate_proof | aggregate_proof_all | prove_groth16 [--verify-air]",
             args[0]
         );
         std::process::exit(-1);
@@ -113,6 +113,10 @@ fn main() {
     if args.len() < 2 {
         helper();
     }
+    if args[2..].iter().any(|arg| arg == "--verify-air") {
+        zkm::arithmetic::api::self_test().expect("arithmetic AIR self-test failed");
+        log::info!("arithmetic AIR self-test passed");
+    }
     match args[1].as_str() {
         "split" => split_elf_into_segs(),
         "prove" => prove_single_seg(),
@@ -44,7 +44,6 @@ pub(crate) fn generate<F: PrimeField64>(lv: &mut [F], filter: usize, left_in: u3
             u32_to_array(&mut lv[OUTPUT_REGISTER], diff);
         }
         IS_ADDU | IS_ADDIU => {
-            // FIXME: add constraints
             let (result, cy) = left_in.overflowing_add(right_in);
             u32_to_array(&mut lv[AUX_INPUT_REGISTER_0], cy as u32);
             u32_to_array(&mut lv[OUTPUT_REGISTER], result);
@@ -56,6 +55,29 @@ pub(crate) fn generate<F: PrimeField64>(lv: &mut [F], filter: usize, left_in: u3
 /// 2^-16 mod (2^64 - 2^32 + 1)
 const GOLDILOCKS_INVERSE_65536: u64 = 18446462594437939201;
 
+/// Constrains r * (r - 1) == 0, i.e. r to be 0 or 1, assuming
+/// filter != 0. The carries here and the results of the comparisons in
+/// slt.rs are all checked through this, so that a result of 2 cannot
+/// be forged under any of their filters.
+pub(crate) fn eval_packed_generic_boolean<P: PackedField>(
+    yield_constr: &mut ConstraintConsumer<P>,
+    filter: P,
+    r: P,
+) {
+    yield_constr.constraint(filter * r * (r - P::ONES));
+}
+
+pub(crate) fn eval_ext_circuit_boolean<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    filter: ExtensionTarget<D>,
+    r: ExtensionTarget<D>,
+) {
+    let t0 = builder.mul_sub_extension(r, r, r);
+    let t = builder.mul_extension(filter, t0);
+    yield_constr.constraint(builder, t);
+}
+
 /// Constrains x + y == z + cy*2^32, assuming filter != 0.
 ///
 /// Set `is_two_row_op=true` to allow the code to be called from the
@@ -131,7 +153,7 @@ pub(crate) fn eval_packed_generic_addcy<P: PackedField>(
             yield_constr.constraint_transition(filter * given_cy[i]);
         }
     } else {
-        yield_constr.constraint(filter * given_cy[0] * (given_cy[0] - P::ONES));
+        eval_packed_generic_boolean(yield_constr, filter, given_cy[0]);
         yield_constr.constraint(filter * (cy - given_cy[0]));
         for i in 1..N_LIMBS {
             yield_constr.constraint(filter * given_cy[i]);
@@ -149,6 +171,8 @@ pub fn eval_packed_generic<P: PackedField>(
     // let is_gt = lv[IS_GT];
     let is_addi = lv[IS_ADDI];
     let is_addiu = lv[IS_ADDIU];
+    let is_addu = lv[IS_ADDU];
+    let is_subu = lv[IS_SUBU];
 
     let in0 = &lv[INPUT_REGISTER_0];
     let in1 = &lv[INPUT_REGISTER_1];
@@ -162,6 +186,8 @@ pub fn eval_packed_generic<P: PackedField>(
     // eval_packed_generic_addcy(yield_constr, is_gt, in0, aux, in1, out, false);
     eval_packed_generic_addcy(yield_constr, is_addi, in0, in1, out, aux, false);
     eval_packed_generic_addcy(yield_constr, is_addiu, in0, in1, out, aux, false);
+    eval_packed_generic_addcy(yield_constr, is_addu, in0, in1, out, aux, false);
+    eval_packed_generic_addcy(yield_constr, is_subu, in1, out, in0, aux, false);
 }
 
 #[allow(clippy::needless_collect)]
@@ -210,10 +236,6 @@ pub(crate) fn eval_ext_circuit_addcy<F: RichField + Extendable<D>, const D: usiz
     let good_cy = builder.sub_extension(cy, given_cy[0]);
     let cy_filter = builder.mul_extension(filter, good_cy);
 
-    // Check given carry is one bit
-    let bit_constr = builder.mul_sub_extension(given_cy[0], given_cy[0], given_cy[0]);
-    let bit_filter = builder.mul_extension(filter, bit_constr);
-
     if is_two_row_op {
         yield_constr.constraint_transition(builder, cy_filter);
         for i in 1..N_LIMBS {
@@ -221,7 +243,7 @@ pub(crate) fn eval_ext_circuit_addcy<F: RichField + Extendable<D>, const D: usiz
             yield_constr.constraint_transition(builder, t);
         }
     } else {
-        yield_constr.constraint(builder, bit_filter);
+        eval_ext_circuit_boolean(builder, yield_constr, filter, given_cy[0]);
         yield_constr.constraint(builder, cy_filter);
         for i in 1..N_LIMBS {
             let t = builder.mul_extension(filter, given_cy[i]);
@@ -241,6 +263,8 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     //let is_gt = lv[IS_GT];
     let is_addi = lv[IS_ADDI];
     let is_addiu = lv[IS_ADDIU];
+    let is_addu = lv[IS_ADDU];
+    let is_subu = lv[IS_SUBU];
 
     let in0 = &lv[INPUT_REGISTER_0];
     let in1 = &lv[INPUT_REGISTER_1];
@@ -253,6 +277,8 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     //eval_ext_circuit_addcy(builder, yield_constr, is_gt, in0, aux, in1, out, false);
     eval_ext_circuit_addcy(builder, yield_constr, is_addi, in0, in1, out, aux, false);
     eval_ext_circuit_addcy(builder, yield_constr, is_addiu, in0, in1, out, aux, false);
+    eval_ext_circuit_addcy(builder, yield_constr, is_addu, in0, in1, out, aux, false);
+    eval_ext_circuit_addcy(builder, yield_constr, is_subu, in1, out, in0, aux, false);
 }
 
 #[cfg(test)]
@@ -263,6 +289,7 @@ mod tests {
     use rand_chacha::ChaCha8Rng;
 
     use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
 
     // TODO: Should be able to refactor this test to apply to all operations.
     #[test]
@@ -281,6 +308,8 @@ mod tests {
         // lv[IS_GT] = F::ZERO;
         lv[IS_ADDI] = F::ZERO;
         lv[IS_ADDIU] = F::ZERO;
+        lv[IS_ADDU] = F::ZERO;
+        lv[IS_SUBU] = F::ZERO;
 
         let mut constrant_consumer = ConstraintConsumer::new(
             vec![GoldilocksField(2), GoldilocksField(3), GoldilocksField(5)],
@@ -302,7 +331,7 @@ mod tests {
         const N_ITERS: usize = 1000;
 
         for _ in 0..N_ITERS {
-            for op_filter in [IS_ADD, IS_SUB, IS_ADDI, IS_ADDIU] {
+            for op_filter in [IS_ADD, IS_SUB, IS_ADDI, IS_ADDIU, IS_ADDU, IS_SUBU] {
                 // set entire row to random 16-bit values
                 let mut lv = [F::default(); NUM_ARITH_COLUMNS]
                     .map(|_| F::from_canonical_u16(rng.gen::<u16>()));
@@ -315,6 +344,8 @@ mod tests {
                 lv[IS_SUB] = F::ZERO;
                 lv[IS_ADDI] = F::ZERO;
                 lv[IS_ADDIU] = F::ZERO;
+                lv[IS_ADDU] = F::ZERO;
+                lv[IS_SUBU] = F::ZERO;
                 lv[op_filter] = F::ONE;
 
                 let left_in = rng.gen::<u32>();
@@ -334,8 +365,8 @@ mod tests {
                 }
 
                 let expected = match op_filter {
-                    IS_ADD | IS_ADDI | IS_ADDIU => left_in.overflowing_add(right_in).0,
-                    IS_SUB => left_in.overflowing_sub(right_in).0,
+                    IS_ADD | IS_ADDI | IS_ADDIU | IS_ADDU => left_in.overflowing_add(right_in).0,
+                    IS_SUB | IS_SUBU => left_in.overflowing_sub(right_in).0,
                     _ => panic!("unrecognised operation"),
                 };
 
@@ -348,4 +379,26 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn boolean_rejects_other_values() {
+        type F = GoldilocksField;
+
+        let holds = |filter: F, r: F| {
+            let mut constraint_consumer = test_constraint_consumer();
+            eval_packed_generic_boolean(&mut constraint_consumer, filter, r);
+            constraint_consumer
+                .constraint_accs
+                .iter()
+                .all(|&acc| acc == F::ZERO)
+        };
+
+        for r in [F::ZERO, F::ONE] {
+            assert!(holds(F::ONE, r));
+        }
+        for r in [F::TWO, F::NEG_ONE, F::from_canonical_u32(1 << 16)] {
+            assert!(!holds(F::ONE, r), "{r}");
+            assert!(holds(F::ZERO, r), "{r}");
+        }
+    }
 }
@@ -0,0 +1,201 @@
+//! A serializable description of the AIR of the arithmetic STARK, for
+//! tools that audit the constraints or re-implement them elsewhere.
+//!
+//! The description is read off the constraints `ArithmeticStark`
+//! already emits: for each constraint, in emission order, the rows it
+//! applies to and its degree in the columns, and for each cross-table
+//! lookup, its columns and filter as linear combinations of the trace
+//! columns. The constraint polynomials themselves are not symbolic in
+//! this crate, so they are not part of the description; see the
+//! operator modules for those.
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, PrimeField64};
+use serde::{Deserialize, Serialize};
+
+use super::arithmetic_stark::{
+    ctl_arithmetic_rows, degree_on_random_rows, random_lde_rows, ArithmeticStark,
+};
+use super::columns::NUM_ARITH_COLUMNS;
+use crate::constraint_consumer::ConstraintConsumer;
+pub use crate::constraint_consumer::ConstraintKind;
+use crate::cross_table_lookup::{Column, TableWithColumns};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::stark::Stark;
+
+type F = GoldilocksField;
+
+/// One constraint of the arithmetic STARK.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintDescription {
+    pub kind: ConstraintKind,
+    /// The degree of the constraint in the trace columns, without the
+    /// row selector.
+    pub degree: usize,
+}
+
+/// A linear combination of the columns of the current and the next row,
+/// with coefficients given as canonical Goldilocks values.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDescription {
+    pub local: Vec<(usize, u64)>,
+    pub next: Vec<(usize, u64)>,
+    pub constant: u64,
+}
+
+/// The arithmetic table's side of a cross-table lookup.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CtlDescription {
+    pub name: String,
+    pub columns: Vec<ColumnDescription>,
+    pub filter: Option<ColumnDescription>,
+}
+
+/// The range check of the arithmetic table: every value of `columns` is
+/// in `table_column`, with multiplicities in `frequencies_column`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LookupDescription {
+    pub columns: Vec<usize>,
+    pub table_column: usize,
+    pub frequencies_column: usize,
+}
+
+/// The AIR of the arithmetic STARK; see the module documentation.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AirDescription {
+    pub num_columns: usize,
+    /// The maximum degree of the constraints, including the selector.
+    pub constraint_degree: usize,
+    pub constraints: Vec<ConstraintDescription>,
+    pub ctls: Vec<CtlDescription>,
+    pub lookups: Vec<LookupDescription>,
+}
+
+impl AirDescription {
+    /// The number of constraints of each degree, indexed by degree.
+    pub fn constraints_by_degree(&self) -> Vec<usize> {
+        let max = self.constraints.iter().map(|c| c.degree).max().unwrap_or(0);
+        let mut counts = vec![0; max + 1];
+        for c in &self.constraints {
+            counts[c.degree] += 1;
+        }
+        counts
+    }
+}
+
+/// The constraints `stark` emits on the frame `(lv, nv)`, in order, with
+/// every row selector set to one.
+fn recorded(stark: &ArithmeticStark<F, 2>, lv: &[F], nv: &[F]) -> Vec<(F, ConstraintKind)> {
+    let frame = StarkFrame::from_values(lv, nv);
+    let mut consumer = ConstraintConsumer::new_recording(vec![F::ONE], F::ONE, F::ONE, F::ONE);
+    stark.eval_packed_base(&frame, &mut consumer);
+    consumer.recorded()
+}
+
+fn describe_column(column: &Column<F>) -> ColumnDescription {
+    let (local, next, constant) = column.terms();
+    let terms = |lc: &[(usize, F)]| lc.iter().map(|&(c, x)| (c, x.to_canonical_u64())).collect();
+    ColumnDescription {
+        local: terms(local),
+        next: terms(next),
+        constant: constant.to_canonical_u64(),
+    }
+}
+
+fn describe_ctl(name: &str, table: TableWithColumns<F>) -> CtlDescription {
+    CtlDescription {
+        name: name.into(),
+        columns: table.columns().iter().map(describe_column).collect(),
+        filter: table.filter_column.as_ref().map(describe_column),
+    }
+}
+
+/// Describe the AIR of the arithmetic STARK. The degrees are found as
+/// in `arithmetic_stark::constraint_degrees`, one constraint at a time.
+pub fn air_description() -> AirDescription {
+    let stark = ArithmeticStark::<F, 2>::default();
+    let (rows, rate_bits) = random_lde_rows();
+    let size = rows.len();
+
+    let values = (0..size)
+        .map(|i| recorded(&stark, &rows[i], &rows[(i + (1 << rate_bits)) % size]))
+        .collect::<Vec<_>>();
+    let constraints = values[0]
+        .iter()
+        .enumerate()
+        .map(|(i, &(_, kind))| ConstraintDescription {
+            kind,
+            degree: degree_on_random_rows(values.iter().map(|row| row[i].0).collect()),
+        })
+        .collect();
+
+    AirDescription {
+        num_columns: NUM_ARITH_COLUMNS,
+        constraint_degree: stark.constraint_degree(),
+        constraints,
+        ctls: vec![describe_ctl("arithmetic", ctl_arithmetic_rows())],
+        lookups: stark
+            .lookups()
+            .into_iter()
+            .map(|lookup| LookupDescription {
+                columns: lookup.columns,
+                table_column: lookup.table_column,
+                frequencies_column: lookup.frequencies_column,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arithmetic::arithmetic_stark::constraint_degrees;
+    use crate::arithmetic::columns::{RANGE_COUNTER, RC_FREQUENCIES, SHARED_COLS};
+
+    #[test]
+    fn air_description_round_trips() {
+        let air = air_description();
+
+        let json = serde_json::to_string(&air).unwrap();
+        let read: AirDescription = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, air);
+
+        // As many constraints as the STARK emits on any frame.
+        let stark = ArithmeticStark::<F, 2>::default();
+        let zeros = [F::ZERO; NUM_ARITH_COLUMNS];
+        let emitted = recorded(&stark, &zeros, &zeros);
+        assert_eq!(air.constraints.len(), emitted.len());
+        assert!(air
+            .constraints
+            .iter()
+            .map(|c| c.kind)
+            .eq(emitted.iter().map(|&(_, kind)| kind)));
+        assert_eq!(
+            air.constraints_by_degree().iter().sum::<usize>(),
+            emitted.len()
+        );
+
+        // The range counter starts at 0 on the first row, and is the
+        // only first-row constraint.
+        let first_rows = air
+            .constraints
+            .iter()
+            .filter(|c| c.kind == ConstraintKind::FirstRow)
+            .count();
+        assert_eq!(first_rows, 1);
+
+        let max_degree = air.constraints.iter().map(|c| c.degree).max();
+        assert_eq!(max_degree, constraint_degrees().into_values().max());
+        assert!(max_degree.unwrap() <= air.constraint_degree);
+
+        assert_eq!(air.ctls.len(), 1);
+        assert_eq!(
+            air.lookups,
+            vec![LookupDescription {
+                columns: SHARED_COLS.collect(),
+                table_column: RANGE_COUNTER,
+                frequencies_column: RC_FREQUENCIES,
+            }]
+        );
+    }
+}
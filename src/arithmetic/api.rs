@@ -0,0 +1,533 @@
+//! Public interface for building arithmetic table rows outside of the
+//! MIPS witness generator, e.g. for fuzzing or for differential testing
+//! against a reference emulator.
+//!
+//! The column layout is deliberately not part of this interface, apart
+//! from `OPERATOR_ID`, which holds `BinaryOperator::id` of the
+//! operation of a row. What callers can rely on is the row convention:
+//!
+//! - every row has `NUM_ARITH_COLUMNS` entries;
+//! - an operation produces one row, or two rows when its constraints
+//!   also read the following row `nv` (DIV, DIVU, SRL(V) and SRA(V),
+//!   but not a shift by zero); the
+//!   second row has every operation filter set to zero;
+//! - two-row operations must be appended in order, first row first,
+//!   and cannot be the last operation in an unpadded trace.
+
+use alloc::collections::BTreeMap;
+
+use anyhow::Result;
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::util::timing::TimingTree;
+
+use super::arithmetic_stark::{self, ArithmeticStark};
+use super::columns::SHIFT_ZERO_FUNCT;
+use super::operator_id::SHIFT_ZERO_ID;
+use super::shift_zero;
+use super::Operation;
+use crate::config::StarkConfig;
+use crate::proof::StarkProof;
+
+pub use super::air::{
+    air_description, AirDescription, ColumnDescription, ConstraintDescription, ConstraintKind,
+    CtlDescription, LookupDescription,
+};
+pub use super::arithmetic_stark::{air_metrics, AirMetrics};
+pub use super::columns::{NUM_ARITH_COLUMNS, OPERATOR_ID};
+pub use super::decode::{DecodedInstruction, NotArithmetic};
+#[cfg(feature = "stats")]
+pub use super::stats::ArithmeticStats;
+pub use super::{ArithmeticError, BinaryOperator, OverflowPolicy, RowCost};
+
+/// The operator of an [`ArithOp`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ArithOperator {
+    Binary(BinaryOperator),
+}
+
+impl ArithOperator {
+    pub(crate) fn of(operation: &Operation) -> Self {
+        match *operation {
+            Operation::BinaryOperation { operator, .. } => ArithOperator::Binary(operator),
+        }
+    }
+}
+
+/// A single arithmetic operation together with its result.
+#[derive(Debug, Clone)]
+pub struct ArithOp {
+    operation: Operation,
+}
+
+impl ArithOp {
+    /// Create the operation `operator(input0, input1)`, computing its
+    /// result as the MIPS instruction would.
+    pub fn binary(operator: BinaryOperator, input0: u32, input1: u32) -> Self {
+        Self {
+            operation: Operation::binary(operator, input0, input1),
+        }
+    }
+
+    pub fn operator(&self) -> ArithOperator {
+        ArithOperator::of(&self.operation)
+    }
+
+    pub fn inputs(&self) -> Vec<u32> {
+        match self.operation {
+            Operation::BinaryOperation { input0, input1, .. } => vec![input0, input1],
+        }
+    }
+
+    /// The `(lo, hi)` result; `hi` is zero except for MULT(U) and
+    /// DIV(U).
+    pub fn result(&self) -> (u32, u32) {
+        self.operation.result()
+    }
+
+    /// The result, or `Err` where MIPS leaves it unpredictable (division
+    /// by zero) or the inputs cannot come from a MIPS instruction, for
+    /// comparing against a reference that models this.
+    pub fn try_result(&self) -> Result<(u32, u32), ArithmeticError> {
+        self.operation.try_result()
+    }
+
+    /// The result under `policy`; `None` for `OverflowPolicy::Checked`
+    /// where the exact result does not fit, see
+    /// `BinaryOperator::result_with_policy`.
+    pub fn result_with_policy(&self, policy: OverflowPolicy) -> Option<(u32, u32)> {
+        match self.operation {
+            Operation::BinaryOperation {
+                operator,
+                input0,
+                input1,
+                ..
+            } => operator.result_with_policy(input0, input1, policy),
+        }
+    }
+
+    /// True if this is an ADD, ADDI or SUB whose signed result
+    /// overflows, which MIPS turns into an arithmetic exception.
+    pub fn signed_overflow(&self) -> bool {
+        self.operation.signed_overflow()
+    }
+}
+
+impl TryFrom<DecodedInstruction> for ArithOp {
+    type Error = NotArithmetic;
+
+    fn try_from(insn: DecodedInstruction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            operation: insn.try_into()?,
+        })
+    }
+}
+
+impl From<ArithOp> for Operation {
+    fn from(op: ArithOp) -> Self {
+        op.operation
+    }
+}
+
+/// Convert `op` into its trace row, plus the following row for
+/// two-row operations.
+pub fn to_trace_rows<F: PrimeField64>(op: &ArithOp) -> (Vec<F>, Option<Vec<F>>) {
+    op.operation.to_rows()
+}
+
+/// The operator of `row`, read off its `OPERATOR_ID`, for printing a
+/// trace. `None` for padding rows and for the second row of a two-row
+/// operation, which have no operation filter set. A shift by zero is
+/// told apart from the other shifts by its funct field.
+pub fn operator_from_row<F: Field>(row: &[F]) -> Option<ArithOperator> {
+    let id = row[OPERATOR_ID];
+    if id == F::from_canonical_u32(SHIFT_ZERO_ID) {
+        return BinaryOperator::all()
+            .iter()
+            .copied()
+            .find(|op| {
+                op.is_simulated()
+                    && row[SHIFT_ZERO_FUNCT] == F::from_canonical_u32(shift_zero::funct(*op))
+            })
+            .map(ArithOperator::Binary);
+    }
+    BinaryOperator::all()
+        .iter()
+        .copied()
+        .find(|op| id == F::from_canonical_u32(op.id()))
+        .map(ArithOperator::Binary)
+}
+
+/// The operations of `trace`, given as rows in trace order as by
+/// `to_trace_rows` or a transposed `generate_trace`, for regenerating
+/// its rows and diffing them against it after a failed proof. Padding
+/// rows are skipped; see `arithmetic::replay` for what comes back.
+pub fn operations_from_trace<F: PrimeField64>(trace: &[Vec<F>]) -> Vec<ArithOp> {
+    super::replay::operations_from_trace(trace)
+        .into_iter()
+        .map(|operation| ArithOp { operation })
+        .collect()
+}
+
+/// `count` operations of `operator` on random but fixed inputs, e.g. to
+/// benchmark proving one operator in isolation; see
+/// `arithmetic::uniform` for the inputs. Two-row operators such as DIV
+/// take `2 * count` rows, as `estimated_rows` reports.
+pub fn uniform_ops(operator: BinaryOperator, count: usize) -> Vec<ArithOp> {
+    super::uniform::uniform_operations(operator, count)
+        .into_iter()
+        .map(|operation| ArithOp { operation })
+        .collect()
+}
+
+/// The trace of `uniform_ops(operator, count)`.
+pub fn generate_uniform_trace<F: RichField, const D: usize>(
+    operator: BinaryOperator,
+    count: usize,
+) -> Vec<PolynomialValues<F>> {
+    generate_trace::<F, D>(uniform_ops(operator, count))
+}
+
+/// The number of rows `ops` occupy in the trace before padding.
+pub fn estimated_rows(ops: &[ArithOp]) -> usize {
+    ops.iter().map(|op| op.operation.num_rows()).sum()
+}
+
+/// Split `ops` into chunks of at most `max_rows` trace rows, in order
+/// and without splitting two-row operations, to prove each chunk
+/// separately. Panics if `max_rows` is less than the rows of one
+/// operation.
+pub fn chunk_operations(ops: Vec<ArithOp>, max_rows: usize) -> Vec<Vec<ArithOp>> {
+    let ops = ops.into_iter().map(Operation::from).collect();
+    super::chunk_operations(ops, max_rows)
+        .into_iter()
+        .map(|chunk| {
+            chunk
+                .into_iter()
+                .map(|operation| ArithOp { operation })
+                .collect()
+        })
+        .collect()
+}
+
+/// The rows and range checks `op` adds to the arithmetic table, e.g.
+/// to bound the trace of a program before proving it. Sum the costs of
+/// several operations with `Iterator::sum`.
+pub fn trace_cost(op: &ArithOp) -> RowCost {
+    super::trace_cost(&op.operation)
+}
+
+/// Write the rows of `ops`, labelled with their operation, to `writer`
+/// as a Parquet file; see `arithmetic::export` for the schema.
+#[cfg(feature = "export-traces")]
+pub fn export_parquet<W: std::io::Write + Send>(ops: &[ArithOp], writer: W) -> Result<()> {
+    let ops: Vec<Operation> = ops.iter().map(|op| op.operation.clone()).collect();
+    super::export::write_parquet(&ops, writer)
+}
+
+/// The maximum constraint degree of each operator module of the
+/// arithmetic STARK, keyed by module name (e.g. `"mul"`, `"div"`).
+pub fn constraint_degrees() -> BTreeMap<&'static str, usize> {
+    arithmetic_stark::constraint_degrees()
+}
+
+/// Evaluate the constraints on a trace of one operation per operator,
+/// and fail if any does not hold. Cheap enough to run once when a
+/// prover starts, to catch a layout or constraint mismatch early.
+pub fn self_test() -> Result<()> {
+    arithmetic_stark::self_test()
+}
+
+/// Generate the full, padded and range-checked arithmetic trace for `ops`.
+pub fn generate_trace<F: RichField, const D: usize>(ops: Vec<ArithOp>) -> Vec<PolynomialValues<F>> {
+    let stark = ArithmeticStark::<F, D>::default();
+    stark.generate_trace(ops.into_iter().map(Operation::from).collect())
+}
+
+/// Pad a trace from `generate_trace` or `TraceBuilder::finish` with
+/// zero rows to `n_rows` rows, e.g. to benchmark FRI parameters at a
+/// fixed trace height. Fails unless `n_rows` is a power of two and at
+/// least the height of `trace`.
+pub fn pad_to<F: RichField>(
+    trace: Vec<PolynomialValues<F>>,
+    n_rows: usize,
+) -> Result<Vec<PolynomialValues<F>>> {
+    arithmetic_stark::pad_to(trace, n_rows)
+}
+
+/// Builds the same trace as `generate_trace` from operations pushed one
+/// at a time, e.g. straight from an instruction trace, without holding
+/// them all in memory first. The rows are written into the columns as
+/// they are pushed, so the builder holds about one copy of the trace;
+/// `generate_trace` also holds the operations. Both generate the rows
+/// into a reused arena rather than allocating them per operation.
+pub struct TraceBuilder<F: RichField> {
+    builder: arithmetic_stark::TraceBuilder<F>,
+}
+
+impl<F: RichField> TraceBuilder<F> {
+    /// An empty builder.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// A builder with room for `rows` trace rows before it reallocates,
+    /// e.g. `estimated_rows` of the operations to come.
+    pub fn with_capacity(rows: usize) -> Self {
+        Self {
+            builder: arithmetic_stark::TraceBuilder::with_capacity(rows),
+        }
+    }
+
+    /// Append the row(s) of `op`.
+    pub fn push(&mut self, op: &ArithOp) {
+        self.builder.push(&op.operation);
+    }
+
+    /// The number of rows pushed so far, before padding.
+    pub fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of operations of each operator pushed so far.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> &ArithmeticStats {
+        self.builder.stats()
+    }
+
+    /// The full, padded and range-checked arithmetic trace.
+    pub fn finish(self) -> Vec<PolynomialValues<F>> {
+        self.builder.finish()
+    }
+}
+
+impl<F: RichField> Default for TraceBuilder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: RichField> Extend<ArithOp> for TraceBuilder<F> {
+    fn extend<I: IntoIterator<Item = ArithOp>>(&mut self, ops: I) {
+        for op in ops {
+            self.push(&op);
+        }
+    }
+}
+
+/// Prove the arithmetic table of `ops` on its own, without the rest of
+/// the zkVM; see `verify_arithmetic`.
+pub fn prove_arithmetic<F, C, const D: usize>(
+    ops: &[ArithOp],
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<StarkProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let ops: Vec<Operation> = ops.iter().map(|op| op.operation.clone()).collect();
+    arithmetic_stark::prove_arithmetic(&ops, config, timing)
+}
+
+/// Like `prove_arithmetic`, but with the trace padded to `n_rows` rows;
+/// see `pad_to`.
+pub fn prove_arithmetic_padded_to<F, C, const D: usize>(
+    ops: &[ArithOp],
+    n_rows: usize,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<StarkProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let ops: Vec<Operation> = ops.iter().map(|op| op.operation.clone()).collect();
+    arithmetic_stark::prove_arithmetic_padded_to(&ops, n_rows, config, timing)
+}
+
+/// Like `prove_arithmetic`, but also returns the number of operations
+/// of each operator in the proven trace.
+#[cfg(feature = "stats")]
+pub fn prove_arithmetic_with_stats<F, C, const D: usize>(
+    ops: &[ArithOp],
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<(StarkProof<F, C, D>, ArithmeticStats)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let ops: Vec<Operation> = ops.iter().map(|op| op.operation.clone()).collect();
+    arithmetic_stark::prove_arithmetic_with_stats(&ops, config, timing)
+}
+
+/// Verify a proof produced by `prove_arithmetic`.
+pub fn verify_arithmetic<F, C, const D: usize>(
+    proof: &StarkProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    arithmetic_stark::verify_arithmetic(proof, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+
+    #[test]
+    fn to_trace_rows_matches_operation() {
+        type F = GoldilocksField;
+
+        let single = ArithOp::binary(BinaryOperator::ADDU, 1, 2);
+        assert_eq!(single.result(), (3, 0));
+        let (row, nv) = to_trace_rows::<F>(&single);
+        assert_eq!(row.len(), NUM_ARITH_COLUMNS);
+        assert!(nv.is_none());
+
+        let double = ArithOp::binary(BinaryOperator::DIVU, 7, 2);
+        assert_eq!(
+            double.operator(),
+            ArithOperator::Binary(BinaryOperator::DIVU)
+        );
+        assert_eq!(double.inputs(), vec![7, 2]);
+        assert_eq!(double.result(), (3, 1));
+        let (_, nv) = to_trace_rows::<F>(&double);
+        assert_eq!(nv.map(|nv| nv.len()), Some(NUM_ARITH_COLUMNS));
+
+        assert_eq!(estimated_rows(&[single, double]), 3);
+    }
+
+    #[test]
+    fn operator_from_row_inverts_row_filter() {
+        type F = GoldilocksField;
+
+        let mul = ArithOp::binary(BinaryOperator::MUL, 6, 7);
+        let (row, _) = to_trace_rows::<F>(&mul);
+        assert_eq!(
+            operator_from_row(&row),
+            Some(ArithOperator::Binary(BinaryOperator::MUL))
+        );
+
+        // Neither padding nor the second row of DIVU has a filter set.
+        assert_eq!(operator_from_row(&[F::ZERO; NUM_ARITH_COLUMNS]), None);
+        let divu = ArithOp::binary(BinaryOperator::DIVU, 7, 2);
+        let (row, nv) = to_trace_rows::<F>(&divu);
+        assert_eq!(operator_from_row(&row), Some(divu.operator()));
+        assert_eq!(operator_from_row(&nv.unwrap()), None);
+
+        let srav = ArithOp::binary(BinaryOperator::SRAV, 5, 32);
+        let (row, _) = to_trace_rows::<F>(&srav);
+        assert_eq!(operator_from_row(&row), Some(srav.operator()));
+
+        for &operator in BinaryOperator::all() {
+            let op = ArithOp::binary(operator, 3, 7);
+            let (row, _) = to_trace_rows::<F>(&op);
+            assert_eq!(operator_from_row(&row), Some(op.operator()));
+        }
+    }
+
+    #[test]
+    fn trace_builder_matches_generate_trace() {
+        type F = GoldilocksField;
+
+        let ops = vec![
+            ArithOp::binary(BinaryOperator::ADDU, 1, 2),
+            ArithOp::binary(BinaryOperator::DIVU, 7, 2),
+            ArithOp::binary(BinaryOperator::SRL, 0x8765_4321, 0),
+            ArithOp::binary(BinaryOperator::MULTU, u32::MAX, u32::MAX),
+        ];
+
+        let mut builder = TraceBuilder::<F>::new();
+        assert!(builder.is_empty());
+        builder.push(&ops[0]);
+        builder.extend(ops[1..].iter().cloned());
+        assert_eq!(builder.len(), estimated_rows(&ops));
+
+        let streamed = builder.finish();
+        let whole = generate_trace::<F, 2>(ops);
+        assert_eq!(streamed.len(), NUM_ARITH_COLUMNS);
+        assert!(streamed
+            .iter()
+            .zip(&whole)
+            .all(|(s, w)| s.values == w.values));
+    }
+
+    #[test]
+    fn try_result_flags_division_by_zero() {
+        for operator in [BinaryOperator::DIV, BinaryOperator::DIVU] {
+            let op = ArithOp::binary(operator, 7, 0);
+            assert_eq!(op.try_result(), Err(ArithmeticError::DivByZero));
+            // The STARK still proves quotient 0 and remainder 7.
+            assert_eq!(op.result(), (0, 7));
+
+            let op = ArithOp::binary(operator, 7, 2);
+            assert_eq!(op.try_result(), Ok(op.result()));
+        }
+        let op = ArithOp::binary(BinaryOperator::ADDU, 7, 0);
+        assert_eq!(op.try_result(), Ok((7, 0)));
+    }
+
+    #[test]
+    fn signed_overflow() {
+        assert!(ArithOp::binary(BinaryOperator::ADD, 0x7FFFFFFF, 1).signed_overflow());
+        assert!(!ArithOp::binary(BinaryOperator::ADD, 0xFFFFFFFF, 1).signed_overflow());
+        assert!(!ArithOp::binary(BinaryOperator::ADDU, 0x7FFFFFFF, 1).signed_overflow());
+        assert!(ArithOp::binary(BinaryOperator::ADDI, 0x80000000, 0xFFFF).signed_overflow());
+        assert!(ArithOp::binary(BinaryOperator::SUB, 0x80000000, 1).signed_overflow());
+        assert!(!ArithOp::binary(BinaryOperator::SUBU, 0x80000000, 1).signed_overflow());
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn proving_reports_operator_counts() -> Result<()> {
+        use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = GoldilocksField;
+
+        let mut ops = vec![];
+        ops.extend((0..5).map(|i| ArithOp::binary(BinaryOperator::ADD, i, 1)));
+        ops.extend((0..3).map(|i| ArithOp::binary(BinaryOperator::MUL, i, 7)));
+        ops.extend((1..3).map(|i| ArithOp::binary(BinaryOperator::DIV, 100, i)));
+        // A shift by zero counts as its shift.
+        ops.push(ArithOp::binary(BinaryOperator::SRL, 0x8000_0000, 0));
+
+        let config = StarkConfig::standard_fast_config();
+        let mut timing = TimingTree::default();
+        let (proof, stats) = prove_arithmetic_with_stats::<F, C, D>(&ops, &config, &mut timing)?;
+        verify_arithmetic(&proof, &config)?;
+
+        let expected = [
+            (ArithOperator::Binary(BinaryOperator::ADD), 5),
+            (ArithOperator::Binary(BinaryOperator::MUL), 3),
+            (ArithOperator::Binary(BinaryOperator::DIV), 2),
+            (ArithOperator::Binary(BinaryOperator::SRL), 1),
+        ];
+        assert_eq!(stats.counts().len(), expected.len());
+        for (operator, count) in expected {
+            assert_eq!(stats.count(operator), count, "{operator:?}");
+        }
+        assert_eq!(stats.count(ArithOperator::Binary(BinaryOperator::SUB)), 0);
+        assert_eq!(stats.total(), ops.len());
+
+        // Streaming the operations counts the same.
+        let mut builder = TraceBuilder::<F>::new();
+        builder.extend(ops);
+        assert_eq!(builder.stats(), &stats);
+        Ok(())
+    }
+}
@@ -1,27 +1,48 @@
-use std::marker::PhantomData;
-use std::ops::Range;
+use alloc::collections::BTreeMap;
+use core::marker::PhantomData;
+use core::ops::Range;
 
+use anyhow::{bail, ensure, Result};
 use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::packed::PackedField;
-use plonky2::field::polynomial::PolynomialValues;
-use plonky2::field::types::Field;
+use plonky2::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use plonky2::field::types::{Field, Sample};
+use plonky2::fri::oracle::PolynomialBatch;
 use plonky2::hash::hash_types::RichField;
+use plonky2::iop::challenger::Challenger;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
 use plonky2::util::transpose;
+#[cfg(feature = "rayon")]
+use plonky2_maybe_rayon::*;
+use plonky2_util::log2_ceil;
 use static_assertions::const_assert;
 
 use super::columns::NUM_ARITH_COLUMNS;
 use super::shift;
 use crate::all_stark::Table;
-use crate::arithmetic::columns::{RANGE_COUNTER, RC_FREQUENCIES, SHARED_COLS};
-//use crate::arithmetic::{addcy, byte, columns, divmod, modular, mul, Operation};
-use crate::arithmetic::{addcy, columns, div, lo_hi, lui, mul, mult, slt, sra, Operation};
+use crate::arithmetic::columns::{NUM_SHARED_COLS, RANGE_COUNTER, RC_FREQUENCIES, SHARED_COLS};
+#[cfg(feature = "stats")]
+use crate::arithmetic::stats::ArithmeticStats;
+use crate::arithmetic::{
+    addcy, columns, div, estimated_rows, filters, lo_hi, lui, mul, mult, operator_id, shift_zero,
+    slt, sra, BinaryOperator, Operation,
+};
+use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::cross_table_lookup::{Column, TableWithColumns};
+use crate::cross_table_lookup::{
+    get_grand_product_challenge_set, Column, CtlData, TableWithColumns,
+};
 use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
 use crate::lookup::Lookup;
+use crate::proof::StarkProof;
+use crate::prover::prove_single_table;
 use crate::stark::Stark;
+use crate::verifier::verify_stark_proof_with_challenges;
 
 /// Link the 16-bit columns of the arithmetic table, split into groups
 /// of N_LIMBS at a time in `regs`, with the corresponding 32-bit
@@ -102,7 +123,17 @@ pub fn ctl_arithmetic_rows<F: Field>() -> TableWithColumns<F> {
         columns::OUTPUT_REGISTER,
     ];
 
-    let filter_column = Some(Column::sum(COMBINED_OPS.iter().map(|(c, _v)| *c)));
+    // A shift by zero has the filter IS_SHIFT_ZERO whatever the shift,
+    // and its funct in SHIFT_ZERO_FUNCT, which is zero in all other rows.
+    let mut opcodes = COMBINED_OPS.to_vec();
+    opcodes.push((columns::SHIFT_ZERO_FUNCT, 1 << 6));
+
+    let filter_column = Some(Column::sum(
+        COMBINED_OPS
+            .iter()
+            .map(|(c, _v)| *c)
+            .chain([columns::IS_SHIFT_ZERO]),
+    ));
 
     // Create the Arithmetic Table whose columns are those of the
     // operations listed in `ops` whose inputs and outputs are given
@@ -111,7 +142,7 @@ pub fn ctl_arithmetic_rows<F: Field>() -> TableWithColumns<F> {
     // is used as the operation filter).
     TableWithColumns::new(
         Table::Arithmetic,
-        cpu_arith_data_link(&COMBINED_OPS, &REGISTER_MAP),
+        cpu_arith_data_link(&opcodes, &REGISTER_MAP),
         filter_column,
     )
 }
@@ -124,36 +155,24 @@ pub struct ArithmeticStark<F, const D: usize> {
 const RANGE_MAX: usize = 1usize << 16; // Range check strict upper bound
 
 impl<F: RichField, const D: usize> ArithmeticStark<F, D> {
-    /// Expects input in *column*-major layout
-    fn generate_range_checks(&self, cols: &mut [Vec<F>]) {
-        debug_assert!(cols.len() == columns::NUM_ARITH_COLUMNS);
-
-        let n_rows = cols[0].len();
-        debug_assert!(cols.iter().all(|col| col.len() == n_rows));
-
-        for i in 0..RANGE_MAX {
-            cols[columns::RANGE_COUNTER][i] = F::from_canonical_usize(i);
-        }
-        for i in RANGE_MAX..n_rows {
-            cols[columns::RANGE_COUNTER][i] = F::from_canonical_usize(RANGE_MAX - 1);
-        }
+    pub(crate) fn generate_trace(&self, operations: Vec<Operation>) -> Vec<PolynomialValues<F>> {
+        self.trace_builder(operations).finish()
+    }
 
-        // Generate the frequencies column.
-        for col in SHARED_COLS {
-            for i in 0..n_rows {
-                let x = cols[col][i].to_canonical_u64() as usize;
-                assert!(
-                    x < RANGE_MAX,
-                    "column value {} exceeds the max range value {}",
-                    x,
-                    RANGE_MAX
-                );
-                cols[RC_FREQUENCIES][x] += F::ONE;
-            }
-        }
+    /// Like `generate_trace`, but also returns the number of operations
+    /// of each operator.
+    #[cfg(feature = "stats")]
+    pub(crate) fn generate_trace_with_stats(
+        &self,
+        operations: Vec<Operation>,
+    ) -> (Vec<PolynomialValues<F>>, ArithmeticStats) {
+        let builder = self.trace_builder(operations);
+        let stats = builder.stats().clone();
+        (builder.finish(), stats)
     }
 
-    pub(crate) fn generate_trace(&self, operations: Vec<Operation>) -> Vec<PolynomialValues<F>> {
+    /// A builder holding the rows of `operations`.
+    fn trace_builder(&self, operations: Vec<Operation>) -> TraceBuilder<F> {
         // The number of rows reserved is the smallest value that's
         // guaranteed to avoid a reallocation: The only ops that use
         // two rows are the modular operations and DIV, so the only
@@ -161,30 +180,220 @@ impl<F: RichField, const D: usize> ArithmeticStark<F, D> {
         // (which is obviously unlikely in normal
         // circumstances). (Also need at least RANGE_MAX rows to
         // accommodate range checks.)
-        let max_rows = std::cmp::max(2 * operations.len(), RANGE_MAX);
-        let mut trace_rows = Vec::with_capacity(max_rows);
+        let max_rows = core::cmp::max(2 * operations.len(), RANGE_MAX);
+        let mut builder = TraceBuilder::with_capacity(max_rows);
+
+        builder.push_rows_of(&operations);
+        #[cfg(feature = "stats")]
+        for operation in &operations {
+            builder.stats.record(operation);
+        }
+
+        builder
+    }
+}
 
-        for op in operations {
-            let (row1, maybe_row2) = op.to_rows();
-            trace_rows.push(row1);
+/// Builds the arithmetic trace one operation at a time, so that the
+/// operations need not be kept in memory, nor the rows in a second,
+/// row-major copy of the trace. Each row goes straight into the
+/// columns, and its range-checked values are counted as it comes in;
+/// `finish` only pads the trace and fills in the range check columns.
+pub(crate) struct TraceBuilder<F> {
+    cols: Vec<Vec<F>>,
+    /// The number of times each value in `0..RANGE_MAX` occurs in
+    /// `SHARED_COLS` so far.
+    frequencies: Vec<u64>,
+    /// The rows of the operations being pushed, recycled from one batch
+    /// to the next.
+    arena: RowArena<F>,
+    #[cfg(feature = "stats")]
+    stats: ArithmeticStats,
+}
+
+impl<F: RichField> TraceBuilder<F> {
+    /// A builder with room for `rows` rows before it reallocates.
+    pub(crate) fn with_capacity(rows: usize) -> Self {
+        Self {
+            cols: (0..NUM_ARITH_COLUMNS)
+                .map(|_| Vec::with_capacity(rows))
+                .collect(),
+            frequencies: vec![0; RANGE_MAX],
+            arena: RowArena::default(),
+            #[cfg(feature = "stats")]
+            stats: ArithmeticStats::default(),
+        }
+    }
 
-            if let Some(row2) = maybe_row2 {
-                trace_rows.push(row2);
+    /// Append the row(s) of `operation`.
+    pub(crate) fn push(&mut self, operation: &Operation) {
+        #[cfg(feature = "stats")]
+        self.stats.record(operation);
+        self.push_rows_of(core::slice::from_ref(operation));
+    }
+
+    /// Append the rows of `operations`, generated `ROW_BATCH` operations
+    /// at a time into the arena of the builder.
+    fn push_rows_of(&mut self, operations: &[Operation]) {
+        // The arena is taken out while its rows are pushed; this moves
+        // its buffers, but does not reallocate them.
+        let mut arena = core::mem::take(&mut self.arena);
+        for batch in operations.chunks(ROW_BATCH) {
+            arena.fill(batch);
+            for row in arena.rows() {
+                self.push_row(row);
             }
         }
+        self.arena = arena;
+    }
+
+    fn push_row(&mut self, row: &[F]) {
+        debug_assert!(row.len() == NUM_ARITH_COLUMNS);
 
+        for col in SHARED_COLS {
+            let x = row[col].to_canonical_u64() as usize;
+            assert!(
+                x < RANGE_MAX,
+                "column value {} exceeds the max range value {}",
+                x,
+                RANGE_MAX
+            );
+            self.frequencies[x] += 1;
+        }
+        for (col, &x) in self.cols.iter_mut().zip(row) {
+            col.push(x);
+        }
+    }
+
+    /// The number of rows pushed so far, before padding.
+    pub(crate) fn len(&self) -> usize {
+        self.cols[0].len()
+    }
+
+    /// The number of operations of each operator pushed so far.
+    #[cfg(feature = "stats")]
+    pub(crate) fn stats(&self) -> &ArithmeticStats {
+        &self.stats
+    }
+
+    /// Pad the trace and generate the range check columns.
+    pub(crate) fn finish(mut self) -> Vec<PolynomialValues<F>> {
         // Pad the trace with zero rows if it doesn't have enough rows
         // to accommodate the range check columns. Also make sure the
         // trace length is a power of two.
-        let padded_len = trace_rows.len().next_power_of_two();
-        for _ in trace_rows.len()..std::cmp::max(padded_len, RANGE_MAX) {
-            trace_rows.push(vec![F::ZERO; columns::NUM_ARITH_COLUMNS]);
+        let n_rows = core::cmp::max(self.len().next_power_of_two(), RANGE_MAX);
+        let padding = n_rows - self.len();
+        for col in &mut self.cols {
+            col.resize(n_rows, F::ZERO);
         }
+        self.frequencies[0] += (padding * NUM_SHARED_COLS) as u64;
 
-        let mut trace_cols = transpose(&trace_rows);
-        self.generate_range_checks(&mut trace_cols);
+        for (i, x) in self.cols[RANGE_COUNTER].iter_mut().enumerate() {
+            *x = F::from_canonical_usize(i.min(RANGE_MAX - 1));
+        }
+        for (x, &count) in self.cols[RC_FREQUENCIES].iter_mut().zip(&self.frequencies) {
+            *x = F::from_canonical_u64(count);
+        }
 
-        trace_cols.into_iter().map(PolynomialValues::new).collect()
+        self.cols.into_iter().map(PolynomialValues::new).collect()
+    }
+}
+
+/// Pad `trace`, as returned by `ArithmeticStark::generate_trace`, with
+/// zero rows to `n_rows` rows, e.g. to compare FRI parameters at a fixed
+/// trace height. The range counter of the new rows stays at its maximum
+/// and their zero limbs are added to the count of 0, so the padded trace
+/// proves the same operations. Fails unless `n_rows` is a power of two
+/// and at least the current height, which is at least `RANGE_MAX` and
+/// the number of rows of the operations.
+pub(crate) fn pad_to<F: Field>(
+    mut trace: Vec<PolynomialValues<F>>,
+    n_rows: usize,
+) -> Result<Vec<PolynomialValues<F>>> {
+    ensure!(
+        trace.len() == NUM_ARITH_COLUMNS,
+        "the trace has {} columns instead of {NUM_ARITH_COLUMNS}",
+        trace.len()
+    );
+    let len = trace[RANGE_COUNTER].len();
+    ensure!(
+        n_rows.is_power_of_two(),
+        "the trace height {n_rows} is not a power of two"
+    );
+    ensure!(
+        n_rows >= len,
+        "cannot pad a trace of {len} rows to {n_rows} rows"
+    );
+
+    let padding = n_rows - len;
+    for (col, values) in trace.iter_mut().enumerate() {
+        let fill = if col == RANGE_COUNTER {
+            F::from_canonical_usize(RANGE_MAX - 1)
+        } else {
+            F::ZERO
+        };
+        values.values.resize(n_rows, fill);
+    }
+    trace[RC_FREQUENCIES].values[0] += F::from_canonical_usize(padding * NUM_SHARED_COLS);
+    Ok(trace)
+}
+
+/// The number of operations whose rows a `RowArena` holds at a time.
+const ROW_BATCH: usize = 1 << 12;
+
+/// The entries of the slot of one operation in a `RowArena`.
+const ROW_SLOT: usize = 2 * NUM_ARITH_COLUMNS;
+
+/// Row-major scratch space for the rows of up to `ROW_BATCH` operations,
+/// filled with `Operation::to_rows_into`, so that generating the trace
+/// allocates two rows per operation of the largest batch instead of
+/// one or two `Vec`s per operation. Each operation gets a slot of two
+/// rows; the second row is only used by the two-row operations. The
+/// slots are overwritten by the next batch, once `TraceBuilder` has
+/// copied the rows into its columns and counted their range checks.
+#[derive(Default)]
+struct RowArena<F> {
+    rows: Vec<F>,
+    /// Whether the slot of each operation holds a second row.
+    has_next: Vec<bool>,
+}
+
+impl<F: RichField> RowArena<F> {
+    /// Replace the rows of the previous batch with those of
+    /// `operations`. Operations are independent of each other, so with
+    /// the `rayon` feature this is done in parallel; the rows are the
+    /// same.
+    fn fill(&mut self, operations: &[Operation]) {
+        debug_assert!(operations.len() <= ROW_BATCH);
+        // `to_rows_into` overwrites every entry, so the old rows need
+        // not be cleared.
+        self.rows.resize(ROW_SLOT * operations.len(), F::ZERO);
+        self.has_next.clear();
+
+        let to_slot = |(slot, op): (&mut [F], &Operation)| {
+            let (row, next) = slot.split_at_mut(NUM_ARITH_COLUMNS);
+            op.to_rows_into(row, next)
+        };
+        #[cfg(feature = "rayon")]
+        self.has_next.par_extend(
+            self.rows
+                .par_chunks_mut(ROW_SLOT)
+                .zip(operations.par_iter())
+                .map(to_slot),
+        );
+        #[cfg(not(feature = "rayon"))]
+        self.has_next
+            .extend(self.rows.chunks_mut(ROW_SLOT).zip(operations).map(to_slot));
+    }
+
+    /// The rows of the batch, in the order of its operations.
+    fn rows(&self) -> impl Iterator<Item = &[F]> {
+        self.rows
+            .chunks(ROW_SLOT)
+            .zip(&self.has_next)
+            .flat_map(|(slot, &has_next)| {
+                let (row, next) = slot.split_at(NUM_ARITH_COLUMNS);
+                core::iter::once(row).chain(has_next.then_some(next))
+            })
     }
 }
 
@@ -228,6 +437,9 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ArithmeticSta
         lui::eval_packed_generic(lv, nv, yield_constr);
         //modular::eval_packed(lv, nv, yield_constr);
         //byte::eval_packed(lv, yield_constr);
+        shift_zero::eval_packed_generic(lv, yield_constr);
+        operator_id::eval_packed_generic(lv, yield_constr);
+        filters::eval_packed_generic(lv, yield_constr);
         shift::eval_packed_generic(lv, nv, yield_constr);
         sra::eval_packed_generic(lv, nv, yield_constr);
         lo_hi::eval_packed_generic(lv, yield_constr);
@@ -266,6 +478,9 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ArithmeticSta
         //divmod::eval_ext_circuit(builder, lv, nv, yield_constr);
         //modular::eval_ext_circuit(builder, lv, nv, yield_constr);
         //byte::eval_ext_circuit(builder, lv, yield_constr);
+        shift_zero::eval_ext_circuit(builder, lv, yield_constr);
+        operator_id::eval_ext_circuit(builder, lv, yield_constr);
+        filters::eval_ext_circuit(builder, lv, yield_constr);
         shift::eval_ext_circuit(builder, lv, nv, yield_constr);
         sra::eval_ext_circuit(builder, lv, nv, yield_constr);
         lo_hi::eval_ext_circuit(builder, lv, yield_constr);
@@ -284,17 +499,363 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ArithmeticSta
     }
 }
 
+/// Prove the arithmetic table of `ops` on its own, without the other
+/// tables of the zkVM and hence without cross-table lookups. The trace
+/// is padded as in `ArithmeticStark::generate_trace`.
+pub(crate) fn prove_arithmetic<F, C, const D: usize>(
+    ops: &[Operation],
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<StarkProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let stark = ArithmeticStark::<F, D>::default();
+    let trace = timed!(
+        timing,
+        "generate arithmetic trace",
+        stark.generate_trace(ops.to_vec())
+    );
+    prove_arithmetic_trace(&stark, trace, config, timing)
+}
+
+/// Like `prove_arithmetic`, but also returns the number of operations
+/// of each operator in the proven trace.
+#[cfg(feature = "stats")]
+pub(crate) fn prove_arithmetic_with_stats<F, C, const D: usize>(
+    ops: &[Operation],
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<(StarkProof<F, C, D>, ArithmeticStats)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let stark = ArithmeticStark::<F, D>::default();
+    let (trace, stats) = timed!(
+        timing,
+        "generate arithmetic trace",
+        stark.generate_trace_with_stats(ops.to_vec())
+    );
+    let proof = prove_arithmetic_trace(&stark, trace, config, timing)?;
+    Ok((proof, stats))
+}
+
+/// Like `prove_arithmetic`, but with the trace padded to `n_rows` rows;
+/// see `pad_to`.
+pub(crate) fn prove_arithmetic_padded_to<F, C, const D: usize>(
+    ops: &[Operation],
+    n_rows: usize,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<StarkProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let stark = ArithmeticStark::<F, D>::default();
+    let trace = timed!(
+        timing,
+        "generate arithmetic trace",
+        stark.generate_trace(ops.to_vec())
+    );
+    let trace = pad_to(trace, n_rows)?;
+    prove_arithmetic_trace(&stark, trace, config, timing)
+}
+
+/// Prove the arithmetic table with the trace `trace`.
+pub(crate) fn prove_arithmetic_trace<F, C, const D: usize>(
+    stark: &ArithmeticStark<F, D>,
+    trace: Vec<PolynomialValues<F>>,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+) -> Result<StarkProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let trace_commitment = timed!(
+        timing,
+        "compute arithmetic trace commitment",
+        PolynomialBatch::<F, C, D>::from_values(
+            trace.clone(),
+            config.fri_config.rate_bits,
+            false,
+            config.fri_config.cap_height,
+            timing,
+            None,
+        )
+    );
+
+    let mut challenger = Challenger::<F, C::Hasher>::new();
+    challenger.observe_cap(&trace_commitment.merkle_tree.cap);
+    // With no CTLs, these are only used by the range check lookup.
+    let ctl_challenges = get_grand_product_challenge_set(&mut challenger, config.num_challenges);
+
+    let proof = prove_single_table(
+        stark,
+        config,
+        &trace,
+        &trace_commitment,
+        &CtlData::default(),
+        &ctl_challenges,
+        &mut challenger,
+        timing,
+    )?;
+    Ok(proof.proof)
+}
+
+/// Verify a proof produced by `prove_arithmetic`.
+pub(crate) fn verify_arithmetic<F, C, const D: usize>(
+    proof: &StarkProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let stark = ArithmeticStark::<F, D>::default();
+
+    let mut challenger = Challenger::<F, C::Hasher>::new();
+    challenger.observe_cap(&proof.trace_cap);
+    let ctl_challenges = get_grand_product_challenge_set(&mut challenger, config.num_challenges);
+    challenger.compact();
+    let challenges = proof.get_challenges(&mut challenger, config);
+
+    verify_stark_proof_with_challenges(&stark, proof, &challenges, &[], &ctl_challenges, config)
+}
+
+/// Evaluate the constraints of the arithmetic STARK on every row of
+/// `trace`, given as columns as returned by
+/// `ArithmeticStark::generate_trace`, with the next row of the last row
+/// being the first. Returns, for each row, its index and the residual of
+/// each constraint in the order `eval_packed_generic` emits them, all of
+/// which are zero for a valid trace. The range check lookup is not
+/// evaluated.
+pub(crate) fn eval_all_rows<F: RichField + Extendable<D>, const D: usize>(
+    trace: &[PolynomialValues<F>],
+) -> Vec<(usize, Vec<F>)> {
+    let stark = ArithmeticStark::<F, D>::default();
+    let cols = trace
+        .iter()
+        .map(|col| col.values.clone())
+        .collect::<Vec<_>>();
+    let rows = transpose(&cols);
+    let n = rows.len();
+
+    (0..n)
+        .map(|i| {
+            let frame = StarkFrame::from_values(&rows[i], &rows[(i + 1) % n]);
+            let is_last = F::from_bool(i == n - 1);
+            let mut consumer = ConstraintConsumer::new_recording(
+                vec![F::ONE],
+                F::ONE - is_last,
+                F::from_bool(i == 0),
+                is_last,
+            );
+            stark.eval_packed_base(&frame, &mut consumer);
+            (i, consumer.residuals())
+        })
+        .collect()
+}
+
+/// One operation per operator, with inputs that every operator
+/// accepts, and a shift by zero for the `IS_SHIFT_ZERO` row.
+fn self_test_operations() -> Vec<Operation> {
+    let binary = BinaryOperator::all()
+        .iter()
+        .map(|&op| Operation::binary(op, 0x8765_4321, 5));
+    let shift_zero = Operation::binary(BinaryOperator::SRL, 0x8765_4321, 0);
+    binary.chain(core::iter::once(shift_zero)).collect()
+}
+
+/// Check that the trace generator and the constraints of the arithmetic
+/// STARK agree, before spending a proving run on a mismatch between
+/// them: generate one operation per operator, evaluate every constraint
+/// on every row of the trace and fail on the first nonzero residual.
+/// Also fails if some operation filter is not set in any row, so that
+/// every operator module is exercised.
+pub(crate) fn self_test() -> Result<()> {
+    type F = GoldilocksField;
+
+    let ops = self_test_operations();
+    let row_ops = ops
+        .iter()
+        .flat_map(|op| core::iter::repeat_n(op, estimated_rows(core::slice::from_ref(op))))
+        .collect::<Vec<_>>();
+    let trace = ArithmeticStark::<F, 2>::default().generate_trace(ops.clone());
+
+    for (i, residuals) in eval_all_rows::<F, 2>(&trace) {
+        if let Some(k) = residuals.iter().position(|r| !r.is_zero()) {
+            match row_ops.get(i) {
+                Some(op) => bail!("constraint {k} does not hold on row {i}, of {op:?}"),
+                None => bail!("constraint {k} does not hold on padding row {i}"),
+            }
+        }
+    }
+    for (filter, column) in trace[..columns::START_SHARED_COLS].iter().enumerate() {
+        ensure!(
+            column.values.contains(&F::ONE),
+            "no row of the self-test sets the filter in column {filter}"
+        );
+    }
+    Ok(())
+}
+
+type EvalPacked<P> =
+    fn(&[P; NUM_ARITH_COLUMNS], &[P; NUM_ARITH_COLUMNS], &mut ConstraintConsumer<P>);
+
+/// The maximum degree of the constraints of each operator module, for
+/// seeing which operators bound `ArithmeticStark::constraint_degree`.
+///
+/// As in `stark_testing::test_stark_low_degree`, the constraints are
+/// evaluated on random polynomials of degree `n - 1`, so that a degree
+/// `k` constraint gives a polynomial of degree `k * (n - 1)`. The
+/// transition and boundary selectors are set to one so they don't add
+/// to the degree.
+pub(crate) fn constraint_degrees() -> BTreeMap<&'static str, usize> {
+    type F = GoldilocksField;
+
+    let evals: [(&'static str, EvalPacked<F>); 12] = [
+        ("addcy", |lv, _, yc| addcy::eval_packed_generic(lv, yc)),
+        ("div", div::eval_packed),
+        ("filters", |lv, _, yc| filters::eval_packed_generic(lv, yc)),
+        ("lo_hi", |lv, _, yc| lo_hi::eval_packed_generic(lv, yc)),
+        ("lui", lui::eval_packed_generic),
+        ("mul", |lv, _, yc| mul::eval_packed_generic(lv, yc)),
+        ("mult", |lv, _, yc| mult::eval_packed_generic(lv, yc)),
+        ("operator_id", |lv, _, yc| {
+            operator_id::eval_packed_generic(lv, yc)
+        }),
+        ("shift", shift::eval_packed_generic),
+        ("shift_zero", |lv, _, yc| {
+            shift_zero::eval_packed_generic(lv, yc)
+        }),
+        ("slt", |lv, _, yc| slt::eval_packed_generic(lv, yc)),
+        ("sra", sra::eval_packed_generic),
+    ];
+
+    let (rows, rate_bits) = random_lde_rows();
+    let size = rows.len();
+    let alpha = F::rand();
+
+    evals
+        .into_iter()
+        .map(|(name, eval)| {
+            let values = (0..size)
+                .map(|i| {
+                    let mut consumer = ConstraintConsumer::new(vec![alpha], F::ONE, F::ONE, F::ONE);
+                    eval(
+                        &rows[i],
+                        &rows[(i + (1 << rate_bits)) % size],
+                        &mut consumer,
+                    );
+                    consumer.accumulators()[0]
+                })
+                .collect();
+            (name, degree_on_random_rows(values))
+        })
+        .collect()
+}
+
+/// The size of the arithmetic table's AIR, for budgeting the FRI
+/// parameters of a proof that includes it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AirMetrics {
+    /// The number of committed trace columns.
+    pub num_columns: usize,
+    /// The number of constraints emitted on each row.
+    pub num_constraints: usize,
+    /// The maximum degree of the constraints, including the selector.
+    pub max_degree: usize,
+    /// The number of columns the table sends to the CPU in
+    /// `all_stark::ctl_arithmetic`, i.e. of `ctl_arithmetic_rows`.
+    pub ctl_columns: usize,
+}
+
+/// The `AirMetrics` of the arithmetic STARK. The constraints are counted
+/// by evaluating them once on a row of zeros; unlike
+/// `constraint_degrees`, nothing is interpolated.
+pub fn air_metrics() -> AirMetrics {
+    type F = GoldilocksField;
+
+    let stark = ArithmeticStark::<F, 2>::default();
+    let zeros = [F::ZERO; NUM_ARITH_COLUMNS];
+    let frame = StarkFrame::from_values(&zeros, &zeros);
+    let mut consumer = ConstraintConsumer::new_recording(vec![F::ONE], F::ONE, F::ONE, F::ONE);
+    stark.eval_packed_base(&frame, &mut consumer);
+
+    AirMetrics {
+        num_columns: NUM_ARITH_COLUMNS,
+        num_constraints: consumer.recorded().len(),
+        max_degree: stark.constraint_degree(),
+        ctl_columns: ctl_arithmetic_rows::<F>().columns().len(),
+    }
+}
+
+/// The number of rows of the random witness of `random_lde_rows`.
+const WITNESS_SIZE: usize = 1 << 5;
+
+/// The rows of the LDE of `NUM_ARITH_COLUMNS` random polynomials of
+/// degree `WITNESS_SIZE - 1`, with the blowup the constraint degree of
+/// `ArithmeticStark` needs, and that blowup's `rate_bits`. The next row
+/// of row `i` is row `i + (1 << rate_bits)`.
+pub(crate) fn random_lde_rows() -> (Vec<[GoldilocksField; NUM_ARITH_COLUMNS]>, usize) {
+    type F = GoldilocksField;
+
+    let stark = ArithmeticStark::<F, 2>::default();
+    let rate_bits = log2_ceil(stark.constraint_degree() + 1);
+    let cols = (0..NUM_ARITH_COLUMNS)
+        .map(|_| {
+            PolynomialCoeffs::new(F::rand_vec(WITNESS_SIZE))
+                .lde(rate_bits)
+                .fft()
+                .values
+        })
+        .collect::<Vec<_>>();
+    let rows = transpose(&cols)
+        .into_iter()
+        .map(|row| <[F; NUM_ARITH_COLUMNS]>::try_from(row).unwrap())
+        .collect();
+    (rows, rate_bits)
+}
+
+/// The degree, in the columns, of a constraint given its values on the
+/// rows of `random_lde_rows`.
+pub(crate) fn degree_on_random_rows(values: Vec<GoldilocksField>) -> usize {
+    let poly = PolynomialValues::new(values);
+    if poly.is_zero() {
+        0
+    } else {
+        poly.degree().div_ceil(WITNESS_SIZE - 1)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use itertools::Itertools;
+    use plonky2::field::extension::Extendable;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::{Field, PrimeField64};
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha8Rng;
 
-    use crate::arithmetic::arithmetic_stark::ArithmeticStark;
-    use crate::arithmetic::columns::OUTPUT_REGISTER;
+    use crate::arithmetic::arithmetic_stark::{
+        air_metrics, constraint_degrees, cpu_arith_data_link, ctl_arithmetic_rows, eval_all_rows,
+        pad_to, prove_arithmetic, prove_arithmetic_padded_to, self_test, self_test_operations,
+        verify_arithmetic, ArithmeticStark, RowArena, TraceBuilder, RANGE_MAX, ROW_BATCH,
+    };
+    use crate::arithmetic::columns::{
+        IS_NOOP, OUTPUT_REGISTER, OUTPUT_REGISTER_HI, RANGE_COUNTER, RC_FREQUENCIES,
+        START_SHARED_COLS,
+    };
     use crate::arithmetic::*;
+    use crate::config::StarkConfig;
+    use crate::stark::Stark;
     use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
 
     #[test]
@@ -310,6 +871,240 @@ mod tests {
         test_stark_low_degree(stark)
     }
 
+    #[test]
+    fn constraint_degrees_within_bound() {
+        let stark = ArithmeticStark::<GoldilocksField, 2>::default();
+        let degrees = constraint_degrees();
+        assert_eq!(degrees.len(), 12);
+        assert_eq!(degrees.values().max(), Some(&stark.constraint_degree()));
+        assert_eq!(degrees["mul"], 3);
+        assert_eq!(degrees["lo_hi"], 2);
+    }
+
+    #[test]
+    fn air_metrics_are_pinned() {
+        // Update these in the same change as the layout or the CTL, so
+        // that the new size shows up in review.
+        let metrics = air_metrics();
+        assert_eq!(metrics.num_columns, 60);
+        assert_eq!(metrics.max_degree, 3);
+        assert_eq!(metrics.ctl_columns, 4);
+        assert_eq!(
+            metrics.num_constraints,
+            crate::arithmetic::air::air_description().constraints.len()
+        );
+        assert_eq!(
+            metrics.max_degree,
+            constraint_degrees().into_values().max().unwrap()
+        );
+    }
+
+    #[test]
+    fn eval_all_rows_of_valid_trace_is_zero() {
+        type F = GoldilocksField;
+        let ops = vec![
+            Operation::binary(BinaryOperator::ADD, 123, 456),
+            Operation::binary(BinaryOperator::DIV, -7i32 as u32, 2),
+            Operation::binary(BinaryOperator::MULT, 2, 3),
+            Operation::binary(BinaryOperator::SRL, 0x8000_0000, 31),
+        ];
+        let mut trace = ArithmeticStark::<F, 2>::default().generate_trace(ops);
+        let rows = eval_all_rows::<F, 2>(&trace);
+        assert_eq!(rows.len(), trace[0].len());
+        assert!(rows
+            .iter()
+            .all(|(_, residuals)| !residuals.is_empty() && residuals.iter().all(|r| r.is_zero())));
+
+        // A wrong ADD output shows up in the row of the ADD.
+        trace[OUTPUT_REGISTER.start].values[0] += F::ONE;
+        let rows = eval_all_rows::<F, 2>(&trace);
+        let first_bad = rows
+            .iter()
+            .find(|(_, residuals)| residuals.iter().any(|r| !r.is_zero()));
+        assert_eq!(first_bad.map(|&(i, _)| i), Some(0));
+    }
+
+    #[test]
+    fn noop_rows_leave_other_operations_valid() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let noop = || Operation::binary(BinaryOperator::NOOP, 0, 0);
+        let ops = vec![
+            noop(),
+            Operation::binary(BinaryOperator::ADD, 123, 456),
+            noop(),
+            Operation::binary(BinaryOperator::DIV, -7i32 as u32, 2),
+            noop(),
+            noop(),
+            Operation::binary(BinaryOperator::MULT, 2, 3),
+            Operation::binary(BinaryOperator::SRL, 0x8000_0000, 31),
+            noop(),
+        ];
+        let (lv, nv) = noop().to_rows::<F>();
+        assert!(nv.is_none());
+        assert!(lv
+            .iter()
+            .enumerate()
+            .all(|(i, &x)| x == if i == IS_NOOP { F::ONE } else { F::ZERO }));
+
+        let trace = ArithmeticStark::<F, D>::default().generate_trace(ops.clone());
+        assert!(eval_all_rows::<F, D>(&trace)
+            .iter()
+            .all(|(_, residuals)| residuals.iter().all(|r| r.is_zero())));
+
+        let config = StarkConfig::standard_fast_config();
+        let mut timing = TimingTree::default();
+        let proof = prove_arithmetic::<F, C, D>(&ops, &config, &mut timing)?;
+        verify_arithmetic(&proof, &config)
+    }
+
+    #[test]
+    fn chunks_prove_the_whole_batch() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let ops = vec![
+            Operation::binary(BinaryOperator::ADDU, 1, 2),
+            Operation::binary(BinaryOperator::DIVU, 7, 2),
+            Operation::binary(BinaryOperator::MUL, 6, 7),
+            Operation::binary(BinaryOperator::DIV, 7, 2),
+            Operation::binary(BinaryOperator::SRA, 0x8000_0000, 4),
+            Operation::binary(BinaryOperator::SLT, 1, 2),
+        ];
+        let max_rows = 4;
+        let chunks = chunk_operations(ops.clone(), max_rows);
+        // The DIV does not fit after ADDU, DIVU and MUL.
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
+        assert_eq!(chunks, chunk_operations(ops.clone(), max_rows));
+        assert_eq!(chunks.concat(), ops);
+
+        let stark = ArithmeticStark::<F, D>::default();
+        let whole = stark.generate_trace(ops.clone());
+        let mut row = 0;
+        let config = StarkConfig::standard_fast_config();
+        for chunk in &chunks {
+            let rows = estimated_rows(chunk);
+            assert!(rows <= max_rows);
+
+            // Apart from the range check, the chunk's rows are those of
+            // the whole batch.
+            let trace = stark.generate_trace(chunk.clone());
+            for (col, (part, all)) in trace.iter().zip(&whole).enumerate() {
+                if col == RANGE_COUNTER || col == RC_FREQUENCIES {
+                    continue;
+                }
+                assert_eq!(
+                    part.values[..rows],
+                    all.values[row..row + rows],
+                    "column {col}"
+                );
+            }
+            row += rows;
+
+            let mut timing = TimingTree::default();
+            let proof = prove_arithmetic::<F, C, D>(chunk, &config, &mut timing)?;
+            verify_arithmetic(&proof, &config)?;
+        }
+        assert_eq!(row, estimated_rows(&ops));
+        Ok(())
+    }
+
+    #[test]
+    fn self_test_passes() {
+        self_test().unwrap();
+    }
+
+    #[test]
+    fn self_test_covers_every_filter() {
+        type F = GoldilocksField;
+        let ops = self_test_operations();
+        let rows = ops.iter().map(|op| op.to_rows::<F>().0).collect::<Vec<_>>();
+        for filter in 0..START_SHARED_COLS {
+            let count = rows.iter().filter(|row| row[filter] == F::ONE).count();
+            assert_eq!(count, 1, "filter column {filter}");
+        }
+    }
+
+    #[test]
+    fn shift_by_zero_takes_one_row_and_verifies() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let value = 0x8765_4321;
+        let srl = Operation::binary(BinaryOperator::SRL, value, 0);
+        assert_eq!(srl.result(), (value, 0));
+        let (lv, nv) = srl.to_rows::<F>();
+        assert!(nv.is_none());
+        assert_eq!(lv[columns::IS_SHIFT_ZERO], F::ONE);
+        assert_eq!(lv[columns::IS_SRL], F::ZERO);
+
+        // The CPU lookup still sees an SRL.
+        let ctl = ctl_arithmetic_rows::<F>();
+        let opcode = ctl.columns()[0].eval_with_next::<F, F, 1>(&lv, &lv);
+        assert_eq!(opcode, F::from_canonical_u32(0b000010 << 6));
+        let filter = ctl.filter_column.as_ref().unwrap();
+        assert_eq!(filter.eval_with_next::<F, F, 1>(&lv, &lv), F::ONE);
+
+        // A shift by 32 takes the two rows of SRAV, which keep the
+        // amount the CPU sends.
+        let ops = vec![
+            srl,
+            Operation::binary(BinaryOperator::SRAV, value, 32),
+            Operation::binary(BinaryOperator::SLL, value, 0),
+            Operation::binary(BinaryOperator::SRL, value, 4),
+        ];
+        assert_eq!(estimated_rows(&ops), 6);
+        let config = StarkConfig::standard_fast_config();
+        let mut timing = TimingTree::default();
+        let proof = prove_arithmetic::<F, C, D>(&ops, &config, &mut timing)?;
+        verify_arithmetic(&proof, &config)
+    }
+
+    #[test]
+    fn ctl_columns_match_limb_accessors() {
+        type F = GoldilocksField;
+        let word = |limbs: [F; columns::N_LIMBS]| {
+            (limbs[0] + limbs[1] * F::from_canonical_u64(1 << columns::LIMB_BITS))
+                .to_canonical_u64() as u32
+        };
+
+        let (row, _) =
+            Operation::binary(BinaryOperator::MULTU, 0x1234_5678, 0x9abc_def0).to_rows::<F>();
+        // [opcode, input0, input1, lo, hi], as the CTLs build them.
+        let link = cpu_arith_data_link::<F>(
+            &[(columns::IS_MULTU, 0)],
+            &[
+                columns::INPUT_REGISTER_0,
+                columns::INPUT_REGISTER_1,
+                columns::OUTPUT_REGISTER_LO,
+                columns::OUTPUT_REGISTER_HI,
+            ],
+        );
+        let expected = [
+            columns::get_input0_limbs(&row),
+            columns::get_input1_limbs(&row),
+            columns::get_result_limbs(&row),
+            columns::get_result_hi_limbs(&row),
+        ]
+        .map(word);
+        let linked = link[1..]
+            .iter()
+            .map(|c| c.eval_with_next::<F, F, 1>(&row, &row).to_canonical_u64() as u32)
+            .collect_vec();
+        assert_eq!(linked, expected);
+        assert_eq!(
+            expected,
+            [0x1234_5678, 0x9abc_def0, 0x242d_2080, 0x0b00_ea4e]
+        );
+    }
+
     #[test]
     fn circuit() -> Result<()> {
         const D: usize = 2;
@@ -350,6 +1145,14 @@ mod tests {
         let mult1 = Operation::binary(BinaryOperator::MULT, -123i32 as u32, 456);
         // 123 * 456 == 56088
         let multu = Operation::binary(BinaryOperator::MULTU, 123, 456);
+        // 0xFFFFFFFF * 0xFFFFFFFF == 0xFFFFFFFE_00000001
+        let multu_max = Operation::binary(BinaryOperator::MULTU, u32::MAX, u32::MAX);
+        // -1 * -1 == 1
+        let mult_neg = Operation::binary(BinaryOperator::MULT, -1i32 as u32, -1i32 as u32);
+        // -5 / 0 == 0, remainder -5
+        let div_zero = Operation::binary(BinaryOperator::DIV, -5i32 as u32, 0);
+        // i32::MIN / -1 == i32::MIN
+        let div_overflow = Operation::binary(BinaryOperator::DIV, i32::MIN as u32, -1i32 as u32);
 
         // 128 < 13 == 0
         //let lt1 = Operation::binary(BinaryOperator::Lt, 128, 13);
@@ -361,7 +1164,20 @@ mod tests {
         // byte(30, 0xABCD) = 0xAB
         // let byte = Operation::binary(BinaryOperator::Byte, U256::from(30), U256::from(0xABCD));
 
-        let ops: Vec<Operation> = vec![add, mul, div0, div1, divu, mult0, mult1, multu];
+        let ops: Vec<Operation> = vec![
+            add,
+            mul,
+            div0,
+            div1,
+            divu,
+            mult0,
+            mult1,
+            multu,
+            multu_max,
+            mult_neg,
+            div_zero,
+            div_overflow,
+        ];
 
         let pols = stark.generate_trace(ops);
 
@@ -384,8 +1200,30 @@ mod tests {
             (8, [56088, 0]),
             (9, [9448, 65535]),
             (10, [56088, 0]),
+            (11, [1, 0]),
+            (12, [1, 0]),
+            (13, [0, 0]),
+            (15, [0, 32768]),
         ];
 
+        // MULT/MULTU also carry the high word of the product, DIV the remainder.
+        let expected_output_hi = [
+            (11, [65534u64, 65535]),
+            (12, [0, 0]),
+            (13, [65531, 65535]),
+            (15, [0, 0]),
+        ];
+        for (row, expected) in expected_output_hi {
+            for (expected, col) in expected.into_iter().zip_eq(OUTPUT_REGISTER_HI) {
+                let out = pols[col].values[row].to_canonical_u64();
+                assert_eq!(
+                    out, expected,
+                    "expected column {} on row {} to be {} but it was {}",
+                    col, row, expected, out,
+                );
+            }
+        }
+
         for (row, expected) in expected_output {
             // OUTPUT registers should match expected value...
             for (expected, col) in expected.into_iter().zip_eq(OUTPUT_REGISTER) {
@@ -399,6 +1237,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn row_arena_keeps_order() {
+        type F = GoldilocksField;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        let operators = [
+            BinaryOperator::ADDU,
+            BinaryOperator::MULT,
+            BinaryOperator::DIVU,
+            BinaryOperator::SRAV,
+            BinaryOperator::SLT,
+        ];
+        let ops = (0..1000)
+            .map(|i| {
+                let operator = operators[i % operators.len()];
+                Operation::binary(operator, rng.gen::<u32>(), rng.gen::<u32>())
+            })
+            .collect::<Vec<_>>();
+
+        let sequential = ops
+            .iter()
+            .flat_map(|op| {
+                let (row1, maybe_row2) = op.to_rows::<F>();
+                core::iter::once(row1).chain(maybe_row2)
+            })
+            .collect::<Vec<_>>();
+        let mut arena = RowArena::default();
+        arena.fill(&ops);
+        assert_eq!(arena.rows().collect::<Vec<_>>(), sequential);
+
+        // A smaller batch reuses the arena without leaving rows behind.
+        arena.fill(&ops[..3]);
+        let n_rows = ops[..3].iter().map(|op| op.num_rows()).sum::<usize>();
+        assert_eq!(arena.rows().count(), n_rows);
+        assert!(arena
+            .rows()
+            .eq(sequential[..n_rows].iter().map(|row| &row[..])));
+    }
+
+    #[test]
+    fn batches_build_the_same_trace() {
+        type F = GoldilocksField;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        let ops = (0..ROW_BATCH + 10)
+            .map(|i| {
+                let operator = if i % 3 == 0 {
+                    BinaryOperator::DIVU
+                } else {
+                    BinaryOperator::ADDU
+                };
+                Operation::binary(operator, rng.gen::<u32>(), rng.gen::<u32>())
+            })
+            .collect::<Vec<_>>();
+
+        let stark = ArithmeticStark::<F, 2>::default();
+        let mut one_at_a_time = TraceBuilder::with_capacity(0);
+        for op in &ops {
+            one_at_a_time.push(op);
+        }
+        assert_eq!(stark.generate_trace(ops), one_at_a_time.finish());
+    }
+
     #[test]
     fn big_traces() {
         const D: usize = 2;
@@ -449,4 +1350,79 @@ mod tests {
         );
         */
     }
+
+    #[test]
+    fn prove_and_verify_arithmetic() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let ops = vec![
+            Operation::binary(BinaryOperator::ADD, 123, 456),
+            Operation::binary(BinaryOperator::SUB, 1, 2),
+            Operation::binary(BinaryOperator::MULT, 0xFFFF_FFFD, 7),
+            Operation::binary(BinaryOperator::DIVU, 0xDEAD_BEEF, 0x1234),
+            Operation::binary(BinaryOperator::SRAV, 0x8765_4321, 12),
+            Operation::binary(BinaryOperator::SLT, 0xFFFF_FFFF, 1),
+            Operation::binary(BinaryOperator::LUI, 0xABCD, 0),
+        ];
+        let config = StarkConfig::standard_fast_config();
+        let mut timing = TimingTree::default();
+
+        let proof = prove_arithmetic::<F, C, D>(&ops, &config, &mut timing)?;
+        verify_arithmetic(&proof, &config)?;
+
+        // A wrong opening must be rejected.
+        let mut bad_proof = proof;
+        bad_proof.openings.local_values[OUTPUT_REGISTER.start] +=
+            <F as Extendable<D>>::Extension::ONE;
+        assert!(verify_arithmetic(&bad_proof, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn padding_preserves_verification() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let ops = vec![
+            Operation::binary(BinaryOperator::ADD, 123, 456),
+            Operation::binary(BinaryOperator::DIVU, 0xDEAD_BEEF, 0x1234),
+            Operation::binary(BinaryOperator::MULTU, u32::MAX, u32::MAX),
+        ];
+        let stark = ArithmeticStark::<F, D>::default();
+        let trace = stark.generate_trace(ops.clone());
+        assert_eq!(trace[0].len(), RANGE_MAX);
+
+        // Padding to the current height changes nothing.
+        let same = pad_to(trace.clone(), RANGE_MAX)?;
+        assert!(same.iter().zip(&trace).all(|(s, t)| s.values == t.values));
+
+        let padded = pad_to(trace, 2 * RANGE_MAX)?;
+        assert!(padded.iter().all(|col| col.len() == 2 * RANGE_MAX));
+        assert!(eval_all_rows::<F, D>(&padded)
+            .iter()
+            .all(|(_, residuals)| residuals.iter().all(|r| r.is_zero())));
+
+        let config = StarkConfig::standard_fast_config();
+        let mut timing = TimingTree::default();
+        let proof =
+            prove_arithmetic_padded_to::<F, C, D>(&ops, 2 * RANGE_MAX, &config, &mut timing)?;
+        assert_eq!(proof.recover_degree_bits(&config), 17);
+        verify_arithmetic(&proof, &config)
+    }
+
+    #[test]
+    fn padding_height_is_validated() {
+        type F = GoldilocksField;
+
+        let ops = vec![Operation::binary(BinaryOperator::ADD, 123, 456)];
+        let trace = ArithmeticStark::<F, 2>::default().generate_trace(ops);
+
+        // Not a power of two.
+        assert!(pad_to(trace.clone(), 3 * RANGE_MAX).is_err());
+        // Shorter than the trace, and so than the range check.
+        assert!(pad_to(trace, RANGE_MAX / 2).is_err());
+    }
 }
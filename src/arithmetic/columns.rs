@@ -1,6 +1,8 @@
 //! Arithmetic unit
 
-use std::ops::Range;
+use core::ops::Range;
+
+use crate::arithmetic::operator_id;
 
 pub const LIMB_BITS: usize = 16;
 const EVM_REGISTER_BITS: usize = 32;
@@ -20,6 +22,13 @@ const fn n_limbs() -> usize {
 }
 
 /// Number of LIMB_BITS limbs that are in on EVM register-sized number.
+///
+/// The limbs of every register of the table are little-endian, so a
+/// register (x_0, x_1) holds the word x_0 + x_1 * 2^16. The cross-table
+/// lookups compare registers with the 32-bit columns of the CPU table
+/// in this order, and the memory table also stores integers
+/// little-endian. The constraints assume it, so words should go in and
+/// out of the table through `utils::pack_u32` and `utils::unpack_u32`.
 pub const N_LIMBS: usize = n_limbs();
 
 pub(crate) const IS_ADD: usize = 0;
@@ -49,7 +58,14 @@ pub(crate) const IS_MTHI: usize = IS_MFHI + 1;
 pub(crate) const IS_MFLO: usize = IS_MTHI + 1;
 pub(crate) const IS_MTLO: usize = IS_MFLO + 1;
 
-pub(crate) const START_SHARED_COLS: usize = IS_MTLO + 1;
+/// Marks a row with no operation. Unlike the all-zero padding rows it
+/// is counted as part of the trace, and no constraints apply to it.
+pub(crate) const IS_NOOP: usize = IS_MTLO + 1;
+/// Marks a shift by zero, which is proven as the identity instead of
+/// with the MUL or DIV of its operator; see `shift_zero`.
+pub(crate) const IS_SHIFT_ZERO: usize = IS_NOOP + 1;
+
+pub(crate) const START_SHARED_COLS: usize = IS_SHIFT_ZERO + 1;
 
 /// Within the Arithmetic Unit, there are shared columns which can be
 /// used by any arithmetic circuit, depending on which one is active
@@ -120,10 +136,21 @@ pub(crate) const MODULAR_DIV_DENOM_IS_ZERO: usize = AUX_REGISTER_2.end;
 pub(crate) const RANGE_COUNTER: usize = START_SHARED_COLS + NUM_SHARED_COLS;
 /// The frequencies column used in logUp.
 pub(crate) const RC_FREQUENCIES: usize = RANGE_COUNTER + 1;
-// These counter columns only used in SRA(V) and DIV, and do not check range
+/// Auxiliary columns outside `SHARED_COLS`, which are not range checked.
+/// SRA(V) and DIV(U) keep intermediate values here, the shifts the bits
+/// of their amount and SLT(I)(U) the inverse of their difference.
 pub(crate) const AUX_EXTRA: Range<usize> = RC_FREQUENCIES + 1..RC_FREQUENCIES + 9;
 
-pub const NUM_ARITH_COLUMNS: usize = START_SHARED_COLS + NUM_SHARED_COLS + 10;
+/// The MIPS funct field of the operator of an `IS_SHIFT_ZERO` row, for
+/// the CPU lookup to tell the shifts apart; zero in every other row.
+pub(crate) const SHIFT_ZERO_FUNCT: usize = AUX_EXTRA.end;
+
+/// The operator of the row as an integer; see `operator_id`.
+pub const OPERATOR_ID: usize = SHIFT_ZERO_FUNCT + 1;
+
+/// The filters and `SHARED_COLS`, then the 2 range check columns, the 8
+/// of `AUX_EXTRA`, `SHIFT_ZERO_FUNCT` and `OPERATOR_ID`.
+pub const NUM_ARITH_COLUMNS: usize = START_SHARED_COLS + NUM_SHARED_COLS + 12;
 
 // These counters are only be used in mul and div that use LO and HI.
 pub(crate) const OUTPUT_REGISTER_LO: Range<usize> = OUTPUT_REGISTER;
@@ -132,3 +159,190 @@ pub(crate) const OUTPUT_REGISTER_HI: Range<usize> =
 pub(crate) const MULT_AUX_LO: Range<usize> =
     OUTPUT_REGISTER_HI.end..OUTPUT_REGISTER_HI.end + 2 * N_LIMBS;
 pub(crate) const MULT_AUX_HI: Range<usize> = MULT_AUX_LO.end..MULT_AUX_LO.end + 2 * N_LIMBS;
+
+fn read_limbs<T: Copy>(row: &[T], reg: Range<usize>) -> [T; N_LIMBS] {
+    debug_assert_eq!(row.len(), NUM_ARITH_COLUMNS);
+    row[reg].try_into().unwrap()
+}
+
+/// The limbs of the first input in the first row of an operation,
+/// least significant first.
+pub fn get_input0_limbs<T: Copy>(row: &[T]) -> [T; N_LIMBS] {
+    read_limbs(row, INPUT_REGISTER_0)
+}
+
+/// The limbs of the second input in the first row of an operation.
+pub fn get_input1_limbs<T: Copy>(row: &[T]) -> [T; N_LIMBS] {
+    read_limbs(row, INPUT_REGISTER_1)
+}
+
+/// The limbs of `INPUT_REGISTER_2`, which holds the third input of
+/// the operations that have one, e.g. `1 << shift` for the shifts.
+pub fn get_input2_limbs<T: Copy>(row: &[T]) -> [T; N_LIMBS] {
+    read_limbs(row, INPUT_REGISTER_2)
+}
+
+/// The limbs of the (low) result in the first row of an operation.
+pub fn get_result_limbs<T: Copy>(row: &[T]) -> [T; N_LIMBS] {
+    read_limbs(row, OUTPUT_REGISTER)
+}
+
+/// The limbs of the high result of MULT(U), which writes HI and LO in
+/// one row.
+pub fn get_result_hi_limbs<T: Copy>(row: &[T]) -> [T; N_LIMBS] {
+    read_limbs(row, OUTPUT_REGISTER_HI)
+}
+
+/// Panic if the columns `cols` are not all in `group`.
+///
+/// Operator modules call this in a `const _: () = ...` item for the
+/// columns they carve out of `SHARED_COLS` and `AUX_EXTRA`, so that a
+/// layout running past its group is a compile error rather than a
+/// trace that silently overwrites another group.
+pub(crate) const fn check_columns(cols: Range<usize>, group: Range<usize>) {
+    if cols.start < group.start || cols.end > group.end {
+        panic!("columns are outside of their group");
+    }
+}
+
+// The groups of `dump_layout` add up to NUM_ARITH_COLUMNS, and the
+// register views don't run past the shared columns.
+const _: () = {
+    assert!(RANGE_COUNTER == SHARED_COLS.end);
+    assert!(RC_FREQUENCIES + 1 == AUX_EXTRA.start);
+    assert!(OPERATOR_ID + 1 == NUM_ARITH_COLUMNS);
+    assert!(
+        START_SHARED_COLS + NUM_SHARED_COLS + 2 + (AUX_EXTRA.end - AUX_EXTRA.start) + 2
+            == NUM_ARITH_COLUMNS
+    );
+    check_columns(AUX_INPUT_REGISTER_2, SHARED_COLS);
+    check_columns(MULT_AUX_HI, SHARED_COLS);
+    check_columns(
+        MODULAR_DIV_DENOM_IS_ZERO..MODULAR_DIV_DENOM_IS_ZERO + 1,
+        SHARED_COLS,
+    );
+};
+
+/// The top-level column groups of the arithmetic table by name, in
+/// order: one entry per operation filter, then `SHARED_COLS`, the
+/// range-check columns, `AUX_EXTRA`, `SHIFT_ZERO_FUNCT` and
+/// `OPERATOR_ID`. Together they partition `0..NUM_ARITH_COLUMNS`. The
+/// register views into `SHARED_COLS` (`INPUT_REGISTER_0`,
+/// `MULT_AUX_LO`, ...) overlap by design and are not listed.
+pub fn dump_layout() -> Vec<(String, Range<usize>)> {
+    let filters = operator_id::filters()
+        .into_iter()
+        .map(|(name, col, _)| (format!("IS_{name}"), col..col + 1));
+    let groups = [
+        ("SHARED_COLS", SHARED_COLS),
+        ("RANGE_COUNTER", RANGE_COUNTER..RANGE_COUNTER + 1),
+        ("RC_FREQUENCIES", RC_FREQUENCIES..RC_FREQUENCIES + 1),
+        ("AUX_EXTRA", AUX_EXTRA),
+        ("SHIFT_ZERO_FUNCT", SHIFT_ZERO_FUNCT..SHIFT_ZERO_FUNCT + 1),
+        ("OPERATOR_ID", OPERATOR_ID..OPERATOR_ID + 1),
+    ];
+
+    filters
+        .chain(groups.map(|(name, range)| (name.to_string(), range)))
+        .collect()
+}
+
+/// The name of every column of the arithmetic table, in order: the
+/// groups of `dump_layout`, with the columns of a group of more than
+/// one column numbered from zero, e.g. `SHARED_COLS[3]`.
+pub fn column_names() -> Vec<String> {
+    dump_layout()
+        .into_iter()
+        .flat_map(|(name, range)| {
+            let len = range.len();
+            (0..len).map(move |i| {
+                if len == 1 {
+                    name.clone()
+                } else {
+                    format!("{name}[{i}]")
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+    use crate::arithmetic::utils::pack_u32;
+    use crate::arithmetic::{BinaryOperator, Operation};
+
+    #[test]
+    fn layout_partitions_the_columns() {
+        let layout = dump_layout();
+        assert_eq!(layout.len(), START_SHARED_COLS + 6);
+
+        let mut next = 0;
+        for (name, range) in &layout {
+            assert!(!range.is_empty(), "{name} is empty");
+            assert_eq!(
+                range.start, next,
+                "{name} does not start where the last group ended"
+            );
+            next = range.end;
+        }
+        assert_eq!(next, NUM_ARITH_COLUMNS);
+
+        for (name, col) in [
+            ("IS_ADD", IS_ADD),
+            ("IS_NOOP", IS_NOOP),
+            ("IS_SHIFT_ZERO", IS_SHIFT_ZERO),
+        ] {
+            assert_eq!(layout[col], (name.to_string(), col..col + 1));
+        }
+    }
+
+    #[test]
+    fn limb_accessors_read_the_registers() {
+        type F = GoldilocksField;
+        let limbs = pack_u32::<F>;
+
+        // 0x1234_5678 * 0x9abc_def0 = 0x0b00_ea4e_242d_2080
+        let (row, _) =
+            Operation::binary(BinaryOperator::MULTU, 0x1234_5678, 0x9abc_def0).to_rows::<F>();
+        assert_eq!(get_input0_limbs(&row), limbs(0x1234_5678));
+        assert_eq!(get_input1_limbs(&row), limbs(0x9abc_def0));
+        assert_eq!(get_result_limbs(&row), limbs(0x242d_2080));
+        assert_eq!(get_result_hi_limbs(&row), limbs(0x0b00_ea4e));
+
+        // SRA by 4 is proven as a division by 1 << 4.
+        let (row, _) = Operation::binary(BinaryOperator::SRA, 0x8000_0000, 4).to_rows::<F>();
+        assert_eq!(get_input2_limbs(&row), limbs(1 << 4));
+        assert_eq!(get_result_limbs(&row), limbs(0xf800_0000));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of their group")]
+    fn over_indexed_columns_are_caught() {
+        check_columns(AUX_EXTRA.start..NUM_ARITH_COLUMNS + 1, AUX_EXTRA);
+    }
+
+    #[test]
+    fn register_views_stay_in_their_groups() {
+        for (name, range) in [
+            ("INPUT_REGISTER_0", INPUT_REGISTER_0),
+            ("INPUT_REGISTER_1", INPUT_REGISTER_1),
+            ("INPUT_REGISTER_2", INPUT_REGISTER_2),
+            ("OUTPUT_REGISTER", OUTPUT_REGISTER),
+            ("AUX_INPUT_REGISTER_0", AUX_INPUT_REGISTER_0),
+            ("AUX_INPUT_REGISTER_1", AUX_INPUT_REGISTER_1),
+            ("AUX_INPUT_REGISTER_2", AUX_INPUT_REGISTER_2),
+            ("AUX_INPUT_REGISTER_DBL", AUX_INPUT_REGISTER_DBL),
+            ("OUTPUT_REGISTER_HI", OUTPUT_REGISTER_HI),
+            ("MULT_AUX_LO", MULT_AUX_LO),
+            ("MULT_AUX_HI", MULT_AUX_HI),
+        ] {
+            assert!(
+                SHARED_COLS.start <= range.start && range.end <= SHARED_COLS.end,
+                "{name} = {range:?} is outside SHARED_COLS = {SHARED_COLS:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Conversion from a MIPS instruction word to the arithmetic table
+//! `Operation` it produces.
+//!
+//! This mirrors the arithmetic cases of `witness::transition::decode`,
+//! but resolves the register operands to their values, so that rows can
+//! be built from an instruction trace without running the witness
+//! generator.
+
+use core::fmt;
+
+use super::{BinaryOperator, Operation};
+use crate::witness::util::sign_extend;
+
+/// The fields of a MIPS instruction word together with the values of
+/// the registers it reads.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DecodedInstruction {
+    pub insn: u32,
+    pub opcode: u8,
+    pub funct: u8,
+    pub rs: u8,
+    pub rt: u8,
+    pub rd: u8,
+    pub sa: u8,
+    pub imm: u16,
+    /// Value of register `rs`.
+    pub rs_value: u32,
+    /// Value of register `rt`.
+    pub rt_value: u32,
+    /// Current LO, read by MFLO.
+    pub lo: u32,
+    /// Current HI, read by MFHI.
+    pub hi: u32,
+}
+
+impl DecodedInstruction {
+    /// Split `insn` into its fields.
+    pub fn new(insn: u32, rs_value: u32, rt_value: u32, lo: u32, hi: u32) -> Self {
+        Self {
+            insn,
+            opcode: ((insn >> 26) & 0x3F) as u8,
+            funct: (insn & 0x3F) as u8,
+            rs: ((insn >> 21) & 0x1F) as u8,
+            rt: ((insn >> 16) & 0x1F) as u8,
+            rd: ((insn >> 11) & 0x1F) as u8,
+            sa: ((insn >> 6) & 0x1F) as u8,
+            imm: (insn & 0xFFFF) as u16,
+            rs_value,
+            rt_value,
+            lo,
+            hi,
+        }
+    }
+}
+
+/// The operator selected by the `funct` field of a SPECIAL (opcode 0)
+/// instruction, if the arithmetic table handles it. A shift funct also
+/// needs the `rs` or `sa` field to be 0.
+pub(crate) const FUNCT_TO_OP: [Option<BinaryOperator>; 64] = {
+    use BinaryOperator::*;
+
+    let mut table = [None; 64];
+    table[0b000000] = Some(SLL);
+    table[0b000010] = Some(SRL);
+    table[0b000011] = Some(SRA);
+    table[0b000100] = Some(SLLV);
+    table[0b000110] = Some(SRLV);
+    table[0b000111] = Some(SRAV);
+    table[0b010000] = Some(MFHI);
+    table[0b010001] = Some(MTHI);
+    table[0b010010] = Some(MFLO);
+    table[0b010011] = Some(MTLO);
+    table[0b011000] = Some(MULT);
+    table[0b011001] = Some(MULTU);
+    table[0b011010] = Some(DIV);
+    table[0b011011] = Some(DIVU);
+    table[0b100000] = Some(ADD);
+    table[0b100001] = Some(ADDU);
+    table[0b100010] = Some(SUB);
+    table[0b100011] = Some(SUBU);
+    table[0b101010] = Some(SLT);
+    table[0b101011] = Some(SLTU);
+    table
+};
+
+/// The instruction word is not one the arithmetic table handles, e.g. a
+/// load, a branch or an undefined encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NotArithmetic(pub u32);
+
+impl fmt::Display for NotArithmetic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x} is not an arithmetic instruction", self.0)
+    }
+}
+
+impl TryFrom<DecodedInstruction> for Operation {
+    type Error = NotArithmetic;
+
+    fn try_from(d: DecodedInstruction) -> Result<Self, Self::Error> {
+        use BinaryOperator::*;
+
+        let (rs, rt, sa) = (d.rs_value, d.rt_value, d.sa as u32);
+        let binary = |operator| Operation::binary(operator, rs, rt);
+
+        let operation = match (d.opcode, d.funct) {
+            (0b000000, funct) => match FUNCT_TO_OP[funct as usize] {
+                // NOP, SSNOP, EHB and PAUSE are `sll $0, $0, sa`, and the
+                // CPU executes them as no-ops, as it does SYNC.
+                Some(SLL) if d.rs == 0 && d.rt == 0 && d.rd == 0 => {
+                    return Err(NotArithmetic(d.insn))
+                }
+                // Shifts take the value to shift first and the amount
+                // second.
+                Some(SLL) if d.rs == 0 => Operation::shift_left(rt, sa),
+                Some(SRL) if d.rs == 0 => Operation::shift_right(rt, sa),
+                Some(SRA) if d.rs == 0 => Operation::binary(SRA, rt, sa),
+                Some(op @ (SLLV | SRLV | SRAV)) if d.sa == 0 => Operation::binary(op, rt, rs),
+                Some(SLL | SRL | SRA | SLLV | SRLV | SRAV) | None => {
+                    return Err(NotArithmetic(d.insn))
+                }
+
+                Some(MFHI) => Operation::binary(MFHI, d.hi, 0),
+                Some(MTHI) => Operation::binary(MTHI, rs, 0),
+                Some(MFLO) => Operation::binary(MFLO, d.lo, 0),
+                Some(MTLO) => Operation::binary(MTLO, rs, 0),
+
+                Some(op) => binary(op),
+            },
+
+            (0b011100, 0b000010) => binary(MUL),
+
+            (0b001000, _) => Operation::binary_imm(ADDI, rs, d.imm),
+            (0b001001, _) => Operation::binary_imm(ADDIU, rs, d.imm),
+            (0b001010, _) => Operation::binary_imm(SLTI, rs, d.imm),
+            (0b001011, _) => Operation::binary_imm(SLTIU, rs, d.imm),
+            // As in `generate_lui`.
+            (0b001111, _) => Operation::binary(LUI, sign_extend::<16>(d.imm as u32), 1 << 16),
+
+            _ => return Err(NotArithmetic(d.insn)),
+        };
+        Ok(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(insn: u32, rs_value: u32, rt_value: u32) -> Result<Operation, NotArithmetic> {
+        DecodedInstruction::new(insn, rs_value, rt_value, 0, 0).try_into()
+    }
+
+    #[test]
+    fn decodes_register_and_shift_encodings() {
+        // addu $3, $1, $2
+        assert_eq!(
+            decode(0x0022_1821, 5, 7),
+            Ok(Operation::binary(BinaryOperator::ADDU, 5, 7))
+        );
+        // sll $3, $2, 4
+        assert_eq!(
+            decode(0x0002_1900, 0, 0x1234),
+            Ok(Operation::binary(BinaryOperator::SLL, 0x1234, 4))
+        );
+        // srl $3, $2, 4
+        assert_eq!(
+            decode(0x0002_1902, 0, 0x1234),
+            Ok(Operation::binary(BinaryOperator::SRL, 0x1234, 4))
+        );
+        // mult $1, $2
+        let mult = decode(0x0022_0018, u32::MAX, 3).unwrap();
+        assert_eq!(mult, Operation::binary(BinaryOperator::MULT, u32::MAX, 3));
+        assert_eq!(mult.result(), (-3i32 as u32, u32::MAX));
+    }
+
+    #[test]
+    fn funct_table_selects_special_operators() {
+        assert_eq!(FUNCT_TO_OP[0x20], Some(BinaryOperator::ADD));
+        assert_eq!(FUNCT_TO_OP[0x22], Some(BinaryOperator::SUB));
+        // jr
+        assert_eq!(FUNCT_TO_OP[0x08], None);
+
+        // add $3, $1, $2 and sub $3, $1, $2
+        assert_eq!(
+            decode(0x0022_1820, 5, 7),
+            Ok(Operation::binary(BinaryOperator::ADD, 5, 7))
+        );
+        assert_eq!(
+            decode(0x0022_1822, 5, 7),
+            Ok(Operation::binary(BinaryOperator::SUB, 5, 7))
+        );
+        // srl $3, $2, 4 with rs = 2 is undefined.
+        assert_eq!(decode(0x0042_1902, 0, 0), Err(NotArithmetic(0x0042_1902)));
+    }
+
+    #[test]
+    fn rejects_non_arithmetic_encodings() {
+        // lw $2, 0($1)
+        assert_eq!(decode(0x8c22_0000, 0, 0), Err(NotArithmetic(0x8c22_0000)));
+        // jr $31
+        assert_eq!(decode(0x03e0_0008, 0, 0), Err(NotArithmetic(0x03e0_0008)));
+    }
+
+    #[test]
+    fn pipeline_hints_are_not_arithmetic() {
+        // nop, ssnop, ehb, pause and sync
+        for insn in [
+            0x0000_0000,
+            0x0000_0040,
+            0x0000_00c0,
+            0x0000_0140,
+            0x0000_000f,
+        ] {
+            assert_eq!(decode(insn, 0, 0), Err(NotArithmetic(insn)), "{insn:#x}");
+        }
+        // sll $3, $0, 0 still writes a register.
+        assert_eq!(
+            decode(0x0000_1800, 0, 0),
+            Ok(Operation::binary(BinaryOperator::SLL, 0, 0))
+        );
+    }
+}
@@ -1,5 +1,24 @@
 //! Support for MIPS instructions DIV and DIVU.
-use std::ops::Range;
+//!
+//! DIV truncates towards zero, so the remainder takes the sign of the
+//! dividend. It is checked on absolute values: the DIVU constraints
+//! give |n| = |q| |d| + |r| with |r| < |d|, and then
+//!
+//!    sign(q) = sign(n) xor sign(d), unless q = 0 or n / d = i32::MIN / -1,
+//!    sign(r) = sign(n), unless r = 0.
+//!
+//! Division by zero gives q = 0 and r = n. The divisor is then
+//! replaced by 1 in the identity n = q d + r, and the selector
+//! `MODULAR_DIV_DENOM_IS_ZERO`, which is set exactly when d = 0, forces
+//! q = 0 so that a prover cannot move part of n from r to q.
+//!
+//! The identity is checked on 16-bit limbs modulo p, so it holds for
+//! limbs that are not 16-bit values too, e.g. q = 3 written as
+//! (3 + 2^16, -1). Soundness therefore rests on the range check: q, r,
+//! their absolute values and the `MODULAR_OUT_AUX_RED` limbs that show
+//! r < d all lie in `SHARED_COLS`, every value of which is looked up in
+//! `0..2^16`.
+use core::ops::Range;
 
 use num::{One, Zero};
 use num_bigint::{BigInt, Sign};
@@ -18,6 +37,28 @@ use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer
 const QUOT_ABS: Range<usize> = AUX_INPUT_REGISTER_2.end..AUX_INPUT_REGISTER_2.end + N_LIMBS;
 const REM_ABS: Range<usize> = QUOT_ABS.end..QUOT_ABS.end + N_LIMBS;
 
+/// Set (in the second row) iff the operation is `i32::MIN / -1`, whose
+/// quotient overflows to `i32::MIN` and so has the "wrong" sign.
+const QUOT_OVERFLOW: usize = RC_FREQUENCIES + 5;
+
+const _: () = {
+    check_columns(
+        OUTPUT_REGISTER_LO.start..OUTPUT_REGISTER_HI.end,
+        SHARED_COLS,
+    );
+    check_columns(QUOT_ABS.start..REM_ABS.end, SHARED_COLS);
+    check_columns(MODULAR_OUT_AUX_RED, SHARED_COLS);
+    check_columns(QUOT_OVERFLOW..QUOT_OVERFLOW + 1, AUX_EXTRA);
+};
+
+/// The input limbs for which `QUOT_OVERFLOW` may be set.
+const QUOT_OVERFLOW_INPUTS: [(usize, u64); 2 * N_LIMBS] = [
+    (INPUT_REGISTER_0.start, 0),
+    (INPUT_REGISTER_0.start + 1, 1 << (LIMB_BITS - 1)),
+    (INPUT_REGISTER_1.start, (1 << LIMB_BITS) - 1),
+    (INPUT_REGISTER_1.start + 1, (1 << LIMB_BITS) - 1),
+];
+
 /// Generate the output and auxiliary values for div/divu operations.
 pub(crate) fn generate<F: PrimeField64>(
     lv: &mut [F],
@@ -106,6 +147,7 @@ pub(crate) fn generate_div<F: PrimeField64>(
         MODULAR_DIV_DENOM_IS_ZERO + 8,
     );
     nv[MODULAR_DIV_DENOM_IS_ZERO + 9] = F::from_bool(is_input0_neg ^ is_input1_neg);
+    nv[QUOT_OVERFLOW] = F::from_bool(input0 == i32::MIN as u32 && input1 == u32::MAX);
 
     fill_columns(
         lv,
@@ -436,6 +478,12 @@ pub(crate) fn modular_constr_poly<P: PackedField>(
                 - div_denom_is_zero),
     );
 
+    // With the modulus replaced by 1, only q + r = n is checked below,
+    // so the quotient must be forced to zero.
+    for &q in &quot[..N_LIMBS] {
+        yield_constr.constraint_transition(filter * div_denom_is_zero * q);
+    }
+
     // Needed to compensate for adding mod_is_zero to modulus above,
     // since the call eval_packed_generic_addcy() below subtracts modulus
     // to verify in the case of a DIV or SHR.
@@ -581,8 +629,20 @@ pub(crate) fn eval_packed_div<P: PackedField>(
                 - P::Scalar::from_canonical_u8(2) * is_input0_neg * is_input1_neg
                 - is_same_sign),
     );
+
+    // quot_overflow can only be set for i32::MIN / -1
+    let quot_overflow = nv[QUOT_OVERFLOW];
+    yield_constr.constraint_transition(filter * quot_overflow * (P::ONES - quot_overflow));
+    for (col, val) in QUOT_OVERFLOW_INPUTS {
+        yield_constr.constraint_transition(
+            filter * quot_overflow * (lv[col] - P::Scalar::from_canonical_u64(val)),
+        );
+    }
+
     let quot_limbs_sum: P = OUTPUT_REGISTER_LO.map(|i| lv[i]).sum();
-    yield_constr.constraint_transition(filter * (is_quot_neg - is_same_sign) * quot_limbs_sum);
+    yield_constr.constraint_transition(
+        filter * (is_quot_neg - is_same_sign - quot_overflow) * quot_limbs_sum,
+    );
 
     // check sign of rem
     // sign(rem) == sign(input0) or rem==0
@@ -676,6 +736,13 @@ pub(crate) fn modular_constr_poly_ext_circuit<F: RichField + Extendable<D>, cons
     let t = builder.mul_extension(filter, t);
     yield_constr.constraint_transition(builder, t);
 
+    // With the modulus replaced by 1, only q + r = n is checked below,
+    // so the quotient must be forced to zero.
+    for &q in &quot[..N_LIMBS] {
+        let t = builder.mul_many_extension([filter, div_denom_is_zero, q]);
+        yield_constr.constraint_transition(builder, t);
+    }
+
     // Needed to compensate for adding mod_is_zero to modulus above,
     // since the call eval_packed_generic_addcy() below subtracts modulus
     // to verify in the case of a DIV or DIVU.
@@ -903,11 +970,27 @@ pub(crate) fn eval_div_ext_circuit<F: RichField + Extendable<D>, const D: usize>
         yield_constr.constraint_transition(builder, t);
     }
 
+    // quot_overflow can only be set for i32::MIN / -1
+    let quot_overflow = nv[QUOT_OVERFLOW];
+    {
+        let one = builder.one_extension();
+        let t0 = builder.sub_extension(one, quot_overflow);
+        let t = builder.mul_many_extension([filter, quot_overflow, t0]);
+        yield_constr.constraint_transition(builder, t);
+    }
+    for (col, val) in QUOT_OVERFLOW_INPUTS {
+        let val = builder.constant_extension(F::Extension::from_canonical_u64(val));
+        let t0 = builder.sub_extension(lv[col], val);
+        let t = builder.mul_many_extension([filter, quot_overflow, t0]);
+        yield_constr.constraint_transition(builder, t);
+    }
+
     let quot_limbs_sum = OUTPUT_REGISTER_LO.fold(builder.zero_extension(), |acc, i| {
         builder.add_extension(acc, lv[i])
     });
 
     let t0 = builder.sub_extension(is_quot_neg, is_same_sign);
+    let t0 = builder.sub_extension(t0, quot_overflow);
     let t = builder.mul_many_extension([filter, t0, quot_limbs_sum]);
     yield_constr.constraint_transition(builder, t);
 
@@ -943,10 +1026,37 @@ mod tests {
     use rand_chacha::ChaCha8Rng;
 
     use super::*;
+    use crate::arithmetic::arithmetic_stark::ArithmeticStark;
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::stark::Stark;
 
     const N_RND_TESTS: usize = 1000;
     const MODULAR_OPS: [usize; 2] = [IS_DIV, IS_DIVU];
 
+    type F = GoldilocksField;
+
+    fn constraints_hold(lv: &[F; NUM_ARITH_COLUMNS], nv: &[F; NUM_ARITH_COLUMNS]) -> bool {
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed(lv, nv, &mut constraint_consumer);
+        constraint_consumer
+            .constraint_accs
+            .iter()
+            .all(|&acc| acc == F::ZERO)
+    }
+
+    /// Whether both rows pass the range check of the arithmetic STARK,
+    /// i.e. every column of its lookup holds a value in `0..2^16`.
+    fn range_check_holds(lv: &[F; NUM_ARITH_COLUMNS], nv: &[F; NUM_ARITH_COLUMNS]) -> bool {
+        let stark = ArithmeticStark::<F, 2>::default();
+        stark.lookups().iter().all(|lookup| {
+            lookup.columns.iter().all(|&c| {
+                [lv[c], nv[c]]
+                    .iter()
+                    .all(|x| x.to_canonical_u64() < (1 << LIMB_BITS))
+            })
+        })
+    }
+
     #[test]
     fn generate_eval_consistency_not_modular() {
         type F = GoldilocksField;
@@ -1008,8 +1118,8 @@ mod tests {
                     (input0 / input1, input0 % input1)
                 } else if op_filter == IS_DIV {
                     (
-                        ((input0 as i32) / (input1 as i32)) as u32,
-                        ((input0 as i32) % (input1 as i32)) as u32,
+                        (input0 as i32).wrapping_div(input1 as i32) as u32,
+                        (input0 as i32).wrapping_rem(input1 as i32) as u32,
                     )
                 } else {
                     panic!()
@@ -1032,6 +1142,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn generate_eval_consistency_signed_edge_cases() {
+        type F = GoldilocksField;
+
+        // (input0, input1, quot, rem)
+        let cases = [
+            // i32::MIN / -1 overflows to i32::MIN
+            (0x80000000u32, 0xFFFFFFFFu32, 0x80000000u32, 0u32),
+            // i32::MIN / 1 == i32::MIN
+            (0x80000000, 0x00000001, 0x80000000, 0),
+            // -7 / 2 == -3 rem -1 (truncation towards zero)
+            (-7i32 as u32, 2, -3i32 as u32, -1i32 as u32),
+            // 7 / -2 == -3 rem 1
+            (7, -2i32 as u32, -3i32 as u32, 1),
+            // -7 / 0 == 0 rem -7
+            (-7i32 as u32, 0, 0, -7i32 as u32),
+        ];
+
+        for (input0, input1, quot, rem) in cases {
+            let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+            let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+            lv[IS_DIV] = F::ONE;
+
+            generate(&mut lv, &mut nv, IS_DIV, input0, input1, quot, rem);
+
+            let mut constraint_consumer = test_constraint_consumer();
+            eval_packed(&lv, &nv, &mut constraint_consumer);
+            for &acc in &constraint_consumer.constraint_accs {
+                assert_eq!(acc, GoldilocksField::ZERO);
+            }
+        }
+    }
+
+    #[test]
+    fn quot_overflow_flag_is_restricted() {
+        type F = GoldilocksField;
+
+        // -8 / 2 == -4; claiming an overflow must not be accepted
+        let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+        let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+        lv[IS_DIV] = F::ONE;
+        generate(&mut lv, &mut nv, IS_DIV, -8i32 as u32, 2, -4i32 as u32, 0);
+        nv[QUOT_OVERFLOW] = F::ONE;
+
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed(&lv, &nv, &mut constraint_consumer);
+        assert!(constraint_consumer
+            .constraint_accs
+            .iter()
+            .any(|&acc| acc != F::ZERO));
+    }
+
+    #[test]
+    fn sign_wrong_remainder_is_rejected() {
+        type F = GoldilocksField;
+
+        // -7 / 2 == -3 rem -1. Claiming rem 1 instead keeps
+        // |-7| = 3 * 2 + |1|, but 1 has the wrong sign.
+        let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+        let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+        lv[IS_DIV] = F::ONE;
+        generate(
+            &mut lv,
+            &mut nv,
+            IS_DIV,
+            -7i32 as u32,
+            2,
+            -3i32 as u32,
+            -1i32 as u32,
+        );
+
+        // Set the remainder and its sign columns as `generate_div`
+        // would for 1; |1| is already in REM_ABS.
+        u32_to_array(&mut lv[OUTPUT_REGISTER_HI], 1);
+        nv[MODULAR_DIV_DENOM_IS_ZERO + 4] = F::from_canonical_u32(0x8000);
+        nv[RC_FREQUENCIES + 3] = F::ZERO;
+        nv[RC_FREQUENCIES + 4] = F::ONE;
+
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed(&lv, &nv, &mut constraint_consumer);
+        assert!(constraint_consumer
+            .constraint_accs
+            .iter()
+            .any(|&acc| acc != F::ZERO));
+    }
+
+    #[test]
+    fn nonzero_quotient_with_zero_divisor_is_rejected() {
+        type F = GoldilocksField;
+
+        for op_filter in MODULAR_OPS {
+            // 7 / 0 == 0 rem 7. Claiming 7 rem 0 instead still gives
+            // q * 1 + r = 7 once the divisor is replaced by 1.
+            let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+            let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+            lv[op_filter] = F::ONE;
+            generate(&mut lv, &mut nv, op_filter, 7, 0, 0, 7);
+            assert_eq!(nv[MODULAR_DIV_DENOM_IS_ZERO], F::ONE);
+
+            u32_to_array(&mut lv[OUTPUT_REGISTER_LO], 7);
+            u32_to_array(&mut lv[OUTPUT_REGISTER_HI], 0);
+            u32_to_array(&mut nv[MODULAR_OUT_AUX_RED], 0);
+            if op_filter == IS_DIV {
+                // The sign columns of 0 and 7 agree, so only the
+                // absolute values change.
+                u32_to_array(&mut lv[QUOT_ABS], 7);
+                u32_to_array(&mut lv[REM_ABS], 0);
+            }
+
+            let mut constraint_consumer = test_constraint_consumer();
+            eval_packed(&lv, &nv, &mut constraint_consumer);
+            assert!(constraint_consumer
+                .constraint_accs
+                .iter()
+                .any(|&acc| acc != F::ZERO));
+        }
+    }
+
     #[test]
     fn zero_modulus() {
         type F = GoldilocksField;
@@ -1064,12 +1292,7 @@ mod tests {
                 // check that the correct output was generated
                 assert!(lv[OUTPUT_REGISTER].iter().all(|&c| c == F::ZERO));
 
-                let mut constraint_consumer = ConstraintConsumer::new(
-                    vec![GoldilocksField(2), GoldilocksField(3), GoldilocksField(5)],
-                    GoldilocksField::ONE,
-                    GoldilocksField::ZERO,
-                    GoldilocksField::ZERO,
-                );
+                let mut constraint_consumer = test_constraint_consumer();
                 eval_packed(&lv, &nv, &mut constraint_consumer);
                 assert!(constraint_consumer
                     .constraint_accs
@@ -1090,4 +1313,58 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn overflowing_quotient_is_rejected() {
+        // 10 / 3 == 3 rem 1.
+        let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+        let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+        lv[IS_DIVU] = F::ONE;
+        generate(&mut lv, &mut nv, IS_DIVU, 10, 3, 3, 1);
+        assert!(constraints_hold(&lv, &nv));
+        assert!(range_check_holds(&lv, &nv));
+
+        // The quotient 3 written as 3 + 2^16 - 2^16, with the auxiliary
+        // polynomial s of (x - β) s(x) = n(x) - c(x) - q(x) m(x)
+        // recomputed to match.
+        let quot = [3 + (1 << LIMB_BITS), -1];
+        let mut constr_poly = [0i64; 2 * N_LIMBS];
+        constr_poly[0] = 10 - 1;
+        let prod = pol_mul_wide2([quot[0], quot[1], 0, 0], [3, 0]);
+        pol_sub_assign(&mut constr_poly, &prod[..2 * N_LIMBS]);
+        let aux = pol_remove_root_2exp::<LIMB_BITS, _, { 2 * N_LIMBS }>(constr_poly);
+        for (i, &q) in OUTPUT_REGISTER_LO.zip(&quot) {
+            lv[i] = F::from_noncanonical_i64(q);
+        }
+        for ((i, j), &c) in MODULAR_AUX_INPUT_LO.zip(MODULAR_AUX_INPUT_HI).zip(&aux) {
+            let c = c + AUX_COEFF_ABS_MAX;
+            nv[i] = F::from_canonical_u16(c as u16);
+            nv[j] = F::from_canonical_u16((c >> 16) as u16);
+        }
+
+        // The polynomial constraints hold in the field; only the range
+        // check of the quotient limbs rejects the rows.
+        assert!(constraints_hold(&lv, &nv));
+        assert!(!range_check_holds(&lv, &nv));
+    }
+
+    #[test]
+    fn unreduced_remainder_is_rejected() {
+        // 10 / 3 == 3 rem 1, claimed as 2 rem 4.
+        let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+        let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+        lv[IS_DIVU] = F::ONE;
+        generate(&mut lv, &mut nv, IS_DIVU, 10, 3, 3, 1);
+        u32_to_array(&mut lv[OUTPUT_REGISTER_LO], 2);
+        u32_to_array(&mut lv[OUTPUT_REGISTER_HI], 4);
+
+        // 2 * 3 + 4 == 10, so only r < d can fail: it is shown by
+        // d + a = r + 2^32 with a in MODULAR_OUT_AUX_RED, and here
+        // a = 2^32 + 1 only fits as the limbs (1, 2^16).
+        nv[MODULAR_OUT_AUX_RED.start] = F::ONE;
+        nv[MODULAR_OUT_AUX_RED.start + 1] = F::from_canonical_u64(1 << LIMB_BITS);
+
+        assert!(constraints_hold(&lv, &nv));
+        assert!(!range_check_holds(&lv, &nv));
+    }
 }
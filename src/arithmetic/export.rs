@@ -0,0 +1,164 @@
+//! Export of the arithmetic trace rows to Parquet, for analysing the
+//! mix of operations in real workloads outside of the prover.
+//!
+//! Every row of `to_rows` becomes one record with the columns
+//!
+//! - `row_index`: the index of the row in the unpadded trace;
+//! - `operator`: the operator name, e.g. `"ADDU"` or `"DIVU"`;
+//! - `op_row`: 0 for the first row of the operation, 1 for the second;
+//! - `input0`, `input1`: the inputs;
+//! - `result0`, `result1`: the `(lo, hi)` result;
+//! - `values`: the `NUM_ARITH_COLUMNS` canonical values of the row.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use anyhow::Result;
+use arrow::array::{
+    ArrayRef, FixedSizeListBuilder, StringBuilder, UInt32Builder, UInt64Builder, UInt8Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::PrimeField64;
+
+use super::columns::NUM_ARITH_COLUMNS;
+use super::Operation;
+
+fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("row_index", DataType::UInt64, false),
+        Field::new("operator", DataType::Utf8, false),
+        Field::new("op_row", DataType::UInt8, false),
+        Field::new("input0", DataType::UInt32, false),
+        Field::new("input1", DataType::UInt32, false),
+        Field::new("result0", DataType::UInt32, false),
+        Field::new("result1", DataType::UInt32, false),
+        Field::new(
+            "values",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::UInt64, true)),
+                NUM_ARITH_COLUMNS as i32,
+            ),
+            false,
+        ),
+    ])
+}
+
+/// The rows of `ops`, in trace order, as one record batch.
+pub(crate) fn trace_batch(ops: &[Operation]) -> Result<RecordBatch> {
+    let mut row_index = UInt64Builder::new();
+    let mut operator = StringBuilder::new();
+    let mut op_row = UInt8Builder::new();
+    let mut inputs = [(); 2].map(|_| UInt32Builder::new());
+    let mut results = [(); 2].map(|_| UInt32Builder::new());
+    let mut values = FixedSizeListBuilder::new(UInt64Builder::new(), NUM_ARITH_COLUMNS as i32);
+
+    let mut index = 0u64;
+    for op in ops {
+        let (name, op_inputs) = match *op {
+            Operation::BinaryOperation {
+                operator,
+                input0,
+                input1,
+                ..
+            } => (format!("{operator:?}"), [input0, input1]),
+        };
+        let (result0, result1) = op.result();
+
+        let (lv, nv) = op.to_rows::<GoldilocksField>();
+        for (i, row) in core::iter::once(lv).chain(nv).enumerate() {
+            row_index.append_value(index);
+            operator.append_value(&name);
+            op_row.append_value(i as u8);
+            for (builder, input) in inputs.iter_mut().zip(op_inputs) {
+                builder.append_value(input);
+            }
+            results[0].append_value(result0);
+            results[1].append_value(result1);
+            for x in row {
+                values.values().append_value(x.to_canonical_u64());
+            }
+            values.append(true);
+            index += 1;
+        }
+    }
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(row_index.finish()),
+        Arc::new(operator.finish()),
+        Arc::new(op_row.finish()),
+    ];
+    columns.extend(
+        inputs
+            .iter_mut()
+            .chain(&mut results)
+            .map(|builder| Arc::new(builder.finish()) as ArrayRef),
+    );
+    columns.push(Arc::new(values.finish()));
+    Ok(RecordBatch::try_new(Arc::new(schema()), columns)?)
+}
+
+/// Write the rows of `ops` to `writer` as a Parquet file.
+pub(crate) fn write_parquet<W: Write + Send>(ops: &[Operation], writer: W) -> Result<()> {
+    let batch = trace_batch(ops)?;
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use arrow::array::{Array, StringArray, UInt32Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::arithmetic::BinaryOperator;
+
+    #[test]
+    fn parquet_round_trip() {
+        let ops = [
+            Operation::binary(BinaryOperator::ADDU, 1, 2),
+            Operation::binary(BinaryOperator::DIVU, 7, 2),
+            Operation::binary(BinaryOperator::MULT, 2, 3),
+        ];
+        let batch = trace_batch(&ops).unwrap();
+        // ADDU and MULT take one row each, DIVU two.
+        assert_eq!(batch.num_rows(), 4);
+
+        let path = std::env::temp_dir().join("zkm_arithmetic_rows_round_trip.parquet");
+        write_parquet(&ops, File::create(&path).unwrap()).unwrap();
+        let read: Vec<RecordBatch> =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].schema().fields(), batch.schema().fields());
+        assert_eq!(read[0].columns(), batch.columns());
+
+        let names = read[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let names: Vec<_> = names.iter().map(Option::unwrap).collect();
+        assert_eq!(names, ["ADDU", "DIVU", "DIVU", "MULT"]);
+
+        let input1 = read[0]
+            .column(4)
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .unwrap();
+        let input1: Vec<_> = input1.iter().map(Option::unwrap).collect();
+        assert_eq!(input1, [2, 2, 2, 3]);
+    }
+}
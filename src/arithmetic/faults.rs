@@ -0,0 +1,156 @@
+//! Traces with injected faults, for testing that proofs of a wrong
+//! arithmetic trace are rejected end to end.
+//!
+//! A `Fault` adds `delta` to one column of one row of every operation of
+//! its operator; the rows are otherwise the ones `Operation::to_rows`
+//! generates. The range check columns are those of the clean trace, so
+//! a fault in a shared column breaks the range check as well as the
+//! constraints of the operator.
+
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+
+use super::api::ArithOperator;
+use super::arithmetic_stark::ArithmeticStark;
+use super::Operation;
+
+/// Add `delta` to `column` of the `row`-th row (0 or 1) of every
+/// operation of `operator`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Fault {
+    pub(crate) operator: ArithOperator,
+    pub(crate) row: usize,
+    pub(crate) column: usize,
+    pub(crate) delta: u64,
+}
+
+/// Generates rows and traces with some `Fault`s applied.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FaultInjector {
+    faults: Vec<Fault>,
+}
+
+impl FaultInjector {
+    pub(crate) fn new(faults: Vec<Fault>) -> Self {
+        Self { faults }
+    }
+
+    /// The faults that apply to `op`.
+    fn faults_of<'a>(&'a self, op: &Operation) -> impl Iterator<Item = &'a Fault> {
+        let operator = ArithOperator::of(op);
+        self.faults.iter().filter(move |f| f.operator == operator)
+    }
+
+    /// Like `op.to_rows()`, with the faults on its operator applied.
+    pub(crate) fn to_rows<F: PrimeField64>(&self, op: &Operation) -> (Vec<F>, Option<Vec<F>>) {
+        let (mut lv, mut nv) = op.to_rows::<F>();
+        for fault in self.faults_of(op) {
+            let row = match (fault.row, nv.as_mut()) {
+                (0, _) => &mut lv,
+                (1, Some(nv)) => nv,
+                _ => panic!("{op:?} has no row {}", fault.row),
+            };
+            row[fault.column] += F::from_noncanonical_u64(fault.delta);
+        }
+        (lv, nv)
+    }
+
+    /// Like `stark.generate_trace(operations)`, with the faults applied
+    /// to the rows of the operations they are on.
+    pub(crate) fn generate_trace<F: RichField, const D: usize>(
+        &self,
+        stark: &ArithmeticStark<F, D>,
+        operations: Vec<Operation>,
+    ) -> Vec<PolynomialValues<F>> {
+        let mut trace = stark.generate_trace(operations.clone());
+        let mut offset = 0;
+        for op in &operations {
+            for fault in self.faults_of(op) {
+                assert!(fault.row < op.num_rows(), "{op:?} has no row {}", fault.row);
+                trace[fault.column].values[offset + fault.row] +=
+                    F::from_noncanonical_u64(fault.delta);
+            }
+            offset += op.num_rows();
+        }
+        trace
+    }
+}
+
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::arithmetic::arithmetic_stark::{prove_arithmetic_trace, verify_arithmetic};
+    use crate::arithmetic::columns::{AUX_INPUT_REGISTER_0, OUTPUT_REGISTER};
+    use crate::arithmetic::BinaryOperator;
+    use crate::config::StarkConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn operations() -> Vec<Operation> {
+        vec![
+            Operation::binary(BinaryOperator::ADD, 123, 456),
+            Operation::binary(BinaryOperator::MUL, 0x1234_5678, 0x9abc_def0),
+            Operation::binary(BinaryOperator::DIVU, 0xdead_beef, 0x1234),
+            Operation::binary(BinaryOperator::MUL, 3, 5),
+        ]
+    }
+
+    fn mul_fault(column: usize) -> Fault {
+        Fault {
+            operator: ArithOperator::Binary(BinaryOperator::MUL),
+            row: 0,
+            column,
+            delta: 1,
+        }
+    }
+
+    /// Whether the trace of `operations` with the faults of `injector`
+    /// is proven and verified. In test builds the prover checks the
+    /// constraints on the trace itself and panics if one does not hold,
+    /// so a panic counts as a rejection too.
+    fn accepted(injector: &FaultInjector, operations: Vec<Operation>) -> bool {
+        let config = StarkConfig::standard_fast_config();
+        let stark = ArithmeticStark::<F, D>::default();
+        let trace = injector.generate_trace(&stark, operations);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let proof = prove_arithmetic_trace::<F, C, D>(
+                &stark,
+                trace,
+                &config,
+                &mut TimingTree::default(),
+            )?;
+            verify_arithmetic(&proof, &config)
+        }));
+        matches!(result, Ok(Ok(())))
+    }
+
+    #[test]
+    fn faults_apply_to_their_operator_only() {
+        let injector = FaultInjector::new(vec![mul_fault(OUTPUT_REGISTER.start)]);
+        for op in operations() {
+            let (mut lv, nv) = op.to_rows::<F>();
+            if ArithOperator::of(&op) == ArithOperator::Binary(BinaryOperator::MUL) {
+                lv[OUTPUT_REGISTER.start] += F::ONE;
+            }
+            assert_eq!(injector.to_rows::<F>(&op), (lv, nv), "{op:?}");
+        }
+    }
+
+    #[test]
+    fn corrupted_mul_is_rejected() {
+        assert!(accepted(&FaultInjector::default(), operations()));
+        // The output of MUL, and one of the auxiliary limbs of its
+        // product.
+        for column in [OUTPUT_REGISTER.start, AUX_INPUT_REGISTER_0.start] {
+            let injector = FaultInjector::new(vec![mul_fault(column)]);
+            assert!(!accepted(&injector, operations()), "column {column}");
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! The operation filters of a row are one-hot: each filter column is 0
+//! or 1, and at most one of them is set. The operator modules rely on
+//! this when they add up the filters of their operators, e.g.
+//! `lv[IS_SLL] + lv[IS_SLLV]`, and so does `OPERATOR_ID`. A row with two
+//! filters set would be checked against, and looked up as, both
+//! operators. `debug_assert_row_order` checks the rows that `to_rows`
+//! generates; the constraints here make the proof check every row.
+//!
+//! With every filter boolean, the sum of the filters is at most one if
+//! and only if it is boolean as well, so we constrain
+//!
+//!    f * (f - 1) = 0   for every filter f, and
+//!    s * (s - 1) = 0   for s = \sum_f f.
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::arithmetic::addcy::{eval_ext_circuit_boolean, eval_packed_generic_boolean};
+use crate::arithmetic::columns::*;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+
+pub(crate) fn eval_packed_generic<P: PackedField>(
+    lv: &[P; NUM_ARITH_COLUMNS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filters = &lv[..START_SHARED_COLS];
+    for &f in filters {
+        eval_packed_generic_boolean(yield_constr, P::ONES, f);
+    }
+    eval_packed_generic_boolean(yield_constr, P::ONES, filters.iter().copied().sum::<P>());
+}
+
+pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let one = builder.one_extension();
+    let filters = &lv[..START_SHARED_COLS];
+    for &f in filters {
+        eval_ext_circuit_boolean(builder, yield_constr, one, f);
+    }
+    let sum = builder.add_many_extension(filters);
+    eval_ext_circuit_boolean(builder, yield_constr, one, sum);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::arithmetic::{BinaryOperator, Operation};
+
+    type F = GoldilocksField;
+
+    fn constraints_hold(lv: &[F]) -> bool {
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(lv.try_into().unwrap(), &mut constraint_consumer);
+        constraint_consumer
+            .constraint_accs
+            .iter()
+            .all(|&acc| acc == F::ZERO)
+    }
+
+    #[test]
+    fn generated_rows_are_one_hot() {
+        let ops = BinaryOperator::all()
+            .iter()
+            .map(|&operator| Operation::binary(operator, 3, 7));
+        for op in ops {
+            let (lv, nv) = op.to_rows::<F>();
+            assert!(constraints_hold(&lv), "{op:?}");
+            if let Some(nv) = nv {
+                assert!(constraints_hold(&nv), "{op:?}");
+            }
+        }
+        // Padding rows have no filter set.
+        assert!(constraints_hold(&[F::ZERO; NUM_ARITH_COLUMNS]));
+    }
+
+    #[test]
+    fn two_filters_are_rejected() {
+        let (lv, _) = Operation::binary(BinaryOperator::ADD, 1, 2).to_rows::<F>();
+        assert!(constraints_hold(&lv));
+
+        for filter in (0..START_SHARED_COLS).filter(|&c| c != IS_ADD) {
+            let mut row = lv.clone();
+            row[filter] = F::ONE;
+            assert!(!constraints_hold(&row), "IS_ADD and filter {filter}");
+        }
+
+        // Nor can the filters sum to one without being boolean.
+        let mut row = lv;
+        row[IS_ADD] = F::TWO;
+        row[IS_SUB] = F::NEG_ONE;
+        assert!(!constraints_hold(&row));
+        row[IS_SUB] = F::ZERO;
+        assert!(!constraints_hold(&row));
+    }
+}
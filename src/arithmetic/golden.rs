@@ -0,0 +1,127 @@
+//! Golden-file test of the arithmetic trace rows.
+//!
+//! `test-vectors/arithmetic_rows.json` holds the rows `to_rows`
+//! produces for one operation of every operator. Any change to the
+//! column layout or to a `generate` function shows up as a diff of that
+//! file. After an intended change, regenerate it with
+//!
+//!    `cargo test --features serde-rows regenerate_goldens -- --ignored`
+//!
+//! and check the diff in review.
+
+use std::fs;
+use std::path::PathBuf;
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::PrimeField64;
+use serde::{Deserialize, Serialize};
+
+use crate::arithmetic::columns::NUM_ARITH_COLUMNS;
+use crate::arithmetic::{BinaryOperator, Operation};
+
+type F = GoldilocksField;
+
+/// An operation and the canonical values of its one or two rows.
+#[derive(Debug, Serialize, Deserialize)]
+struct GoldenRows {
+    operation: Operation,
+    rows: Vec<Vec<u64>>,
+}
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-vectors/arithmetic_rows.json")
+}
+
+/// One operation per operator, with inputs as the witness generator
+/// would pass them.
+fn golden_operations() -> Vec<Operation> {
+    use BinaryOperator::*;
+
+    [
+        (ADD, 0x7fff_ffff, 1),
+        (ADDU, 0xffff_fffe, 3),
+        (ADDI, 5, 0xffff_ffff),
+        (ADDIU, 0x1234_5678, 0xffff_8000),
+        (SUB, 1, 2),
+        (SUBU, 0x8000_0000, 1),
+        (MULT, 0xffff_fffd, 7),
+        (MULTU, 0xffff_ffff, 0xffff_ffff),
+        (MUL, 0x1234_5678, 0x9abc_def0),
+        (DIV, 0xffff_fff9, 2),
+        (DIVU, 0xdead_beef, 0x1234),
+        (SLLV, 0x8765_4321, 36),
+        (SRLV, 0x8765_4321, 8),
+        (SRAV, 0x8765_4321, 12),
+        (SLL, 0x8765_4321, 3),
+        (SRL, 0x8765_4321, 31),
+        (SRA, 0x8765_4321, 17),
+        (SLT, 0xffff_ffff, 1),
+        (SLTU, 0xffff_ffff, 1),
+        (SLTI, 3, 0xffff_fffe),
+        (SLTIU, 3, 0xffff_fffe),
+        (LUI, 0xabcd, 0),
+        (MFHI, 0x0102_0304, 0),
+        (MTHI, 0x0506_0708, 0),
+        (MFLO, 0x090a_0b0c, 0),
+        (MTLO, 0x0d0e_0f10, 0),
+        (NOOP, 0, 0),
+    ]
+    .into_iter()
+    .map(|(operator, input0, input1)| Operation::binary(operator, input0, input1))
+    .collect()
+}
+
+fn generate_goldens() -> Vec<GoldenRows> {
+    golden_operations()
+        .into_iter()
+        .map(|operation| {
+            let (lv, nv) = operation.to_rows::<F>();
+            let rows = std::iter::once(lv)
+                .chain(nv)
+                .map(|row| row.iter().map(|x| x.to_canonical_u64()).collect())
+                .collect();
+            GoldenRows { operation, rows }
+        })
+        .collect()
+}
+
+#[test]
+#[ignore = "writes test-vectors/arithmetic_rows.json"]
+fn regenerate_goldens() {
+    let json = serde_json::to_string_pretty(&generate_goldens()).unwrap();
+    fs::write(golden_path(), json + "\n").unwrap();
+}
+
+#[test]
+fn rows_match_goldens() {
+    let json = fs::read_to_string(golden_path()).unwrap_or_else(|_| {
+        panic!(
+            "missing {}; run `cargo test --features serde-rows regenerate_goldens -- --ignored`",
+            golden_path().display()
+        )
+    });
+    let goldens: Vec<GoldenRows> = serde_json::from_str(&json).unwrap();
+    let fresh = generate_goldens();
+    assert_eq!(goldens.len(), fresh.len(), "number of golden operations");
+
+    for (golden, fresh) in goldens.iter().zip(&fresh) {
+        assert_eq!(golden.operation, fresh.operation);
+        assert_eq!(
+            golden.rows.len(),
+            fresh.rows.len(),
+            "number of rows of {:?}",
+            fresh.operation
+        );
+        for (i, (golden_row, fresh_row)) in golden.rows.iter().zip(&fresh.rows).enumerate() {
+            assert_eq!(fresh_row.len(), NUM_ARITH_COLUMNS);
+            for (col, (g, f)) in golden_row.iter().zip(fresh_row).enumerate() {
+                assert_eq!(
+                    g, f,
+                    "row {} column {} of {:?} changed from {} to {}",
+                    i, col, fresh.operation, g, f
+                );
+            }
+            assert_eq!(golden_row.len(), fresh_row.len(), "column count changed");
+        }
+    }
+}
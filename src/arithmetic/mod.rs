@@ -1,21 +1,42 @@
 pub mod addcy;
+pub mod air;
+pub mod api;
 pub mod arithmetic_stark;
 pub mod columns;
+pub mod decode;
 pub mod div;
+#[cfg(feature = "export-traces")]
+pub mod export;
+#[cfg(test)]
+mod faults;
+pub mod filters;
+#[cfg(all(test, feature = "serde-rows"))]
+mod golden;
 pub mod lo_hi;
 pub mod lui;
 pub mod mul;
 pub mod mult;
+pub mod operator_id;
+pub mod replay;
 pub mod shift;
+pub mod shift_zero;
 pub mod slt;
 pub mod sra;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(test)]
+mod tests;
+pub mod trace_csv;
+pub mod uniform;
 pub mod utils;
 
+use crate::arithmetic::utils::sign_extend_imm16;
 use crate::witness::util::sign_extend;
 use plonky2::field::types::PrimeField64;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) enum BinaryOperator {
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde-rows", derive(serde::Serialize, serde::Deserialize))]
+pub enum BinaryOperator {
     ADD,
     ADDU,
     ADDI,
@@ -42,130 +63,393 @@ pub(crate) enum BinaryOperator {
     MTHI,
     MFLO,
     MTLO,
+    NOOP,
 }
 
+const BINARY_OPERATORS: [BinaryOperator; 27] = [
+    BinaryOperator::ADD,
+    BinaryOperator::ADDU,
+    BinaryOperator::ADDI,
+    BinaryOperator::ADDIU,
+    BinaryOperator::SUB,
+    BinaryOperator::SUBU,
+    BinaryOperator::MULT,
+    BinaryOperator::MULTU,
+    BinaryOperator::MUL,
+    BinaryOperator::DIV,
+    BinaryOperator::DIVU,
+    BinaryOperator::SLLV,
+    BinaryOperator::SRLV,
+    BinaryOperator::SRAV,
+    BinaryOperator::SLL,
+    BinaryOperator::SRL,
+    BinaryOperator::SRA,
+    BinaryOperator::SLT,
+    BinaryOperator::SLTU,
+    BinaryOperator::SLTI,
+    BinaryOperator::SLTIU,
+    BinaryOperator::LUI,
+    BinaryOperator::MFHI,
+    BinaryOperator::MTHI,
+    BinaryOperator::MFLO,
+    BinaryOperator::MTLO,
+    BinaryOperator::NOOP,
+];
+
 impl BinaryOperator {
-    pub(crate) fn result(&self, input0: u32, input1: u32) -> (u32, u32) {
+    /// Every binary operator the arithmetic table supports.
+    pub fn all() -> &'static [BinaryOperator] {
+        &BINARY_OPERATORS
+    }
+
+    /// Whether the operator has no circuit of its own but is proven
+    /// with another one: the shifts SLL(V), SRL(V) and SRA(V) are
+    /// proven as a MUL or DIV by `1 << shift`.
+    pub fn is_simulated(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::SLL
+                | BinaryOperator::SLLV
+                | BinaryOperator::SRL
+                | BinaryOperator::SRLV
+                | BinaryOperator::SRA
+                | BinaryOperator::SRAV
+        )
+    }
+
+    /// Whether the CPU writes the `(lo, hi)` result to the LO and HI
+    /// registers, as for MULT(U) and DIV(U), rather than the first
+    /// result to `rd`. MUL is the three-operand form of MULT and only
+    /// writes the low word of the product to `rd`.
+    pub fn writes_hi_lo(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::MULT
+                | BinaryOperator::MULTU
+                | BinaryOperator::DIV
+                | BinaryOperator::DIVU
+        )
+    }
+
+    /// The `OPERATOR_ID` of the rows of this operator: its position in
+    /// `all()` plus one, as zero marks a row without an operation. A
+    /// shift by zero has `operator_id::SHIFT_ZERO_ID` instead.
+    pub fn id(&self) -> u32 {
+        *self as u32 + 1
+    }
+
+    /// Like `result`, but reports the inputs for which `result` falls
+    /// back to a fixed value instead of returning that value.
+    ///
+    /// For DIV and DIVU with `input1 == 0`, `result` returns quotient
+    /// (LO) 0 and remainder (HI) `input0`, which is what the arithmetic
+    /// table proves; here that case is `Err(ArithmeticError::DivByZero)`.
+    /// Shifts are never out of range, as their amount is masked (see
+    /// `mask_shift_amount`).
+    pub(crate) fn try_result(
+        &self,
+        input0: u32,
+        input1: u32,
+    ) -> Result<(u32, u32), ArithmeticError> {
         match self {
-            BinaryOperator::ADD => (input0.overflowing_add(input1).0, 0),
-            BinaryOperator::ADDU => (input0.overflowing_add(input1).0, 0),
-            BinaryOperator::ADDI => {
-                let sein = sign_extend::<16>(input1);
-                (input0.overflowing_add(sein).0, 0)
-            }
-            BinaryOperator::ADDIU => {
-                let sein = sign_extend::<16>(input1);
-                (input0.overflowing_add(sein).0, 0)
-            }
-            BinaryOperator::SUB => (input0.overflowing_sub(input1).0, 0),
-            BinaryOperator::SUBU => (input0.overflowing_sub(input1).0, 0),
-
-            BinaryOperator::SLL => (if input1 > 31 { 0 } else { input0 << input1 }, 0),
-            BinaryOperator::SRL => (if input1 > 31 { 0 } else { input0 >> input1 }, 0),
-            BinaryOperator::SRA => {
-                let sin = input0 as i32;
-                let sout = if input1 > 31 { 0 } else { sin >> input1 };
-                (sout as u32, 0)
+            BinaryOperator::DIV | BinaryOperator::DIVU if input1 == 0 => {
+                Err(ArithmeticError::DivByZero)
             }
+            _ => Ok(self.result(input0, input1)),
+        }
+    }
+
+    /// `result` under `policy`. With `OverflowPolicy::Wrap` this is
+    /// always `Some(self.result(input0, input1))`. With
+    /// `OverflowPolicy::Checked` it is `None` where the wrapped result
+    /// differs from the exact one, as for Rust's checked arithmetic:
+    /// ADD, ADDI, ADDIU (whose immediate is signed), SUB, MUL and DIV
+    /// overflowing as signed operations, ADDU and SUBU as unsigned ones,
+    /// and the inputs that `try_result` rejects.
+    pub(crate) fn result_with_policy(
+        &self,
+        input0: u32,
+        input1: u32,
+        policy: OverflowPolicy,
+    ) -> Option<(u32, u32)> {
+        match policy {
+            OverflowPolicy::Wrap => Some(self.result(input0, input1)),
+            OverflowPolicy::Checked if self.overflows(input0, input1) => None,
+            OverflowPolicy::Checked => self.try_result(input0, input1).ok(),
+        }
+    }
 
-            BinaryOperator::SLLV => (if input1 > 31 { 0 } else { input0 << input1 }, 0),
-            BinaryOperator::SRLV => (if input1 > 31 { 0 } else { input0 >> input1 }, 0),
-            BinaryOperator::SRAV => {
-                // same as SRA
-                let sin = input0 as i32;
-                let sout = if input1 > 31 { 0 } else { sin >> input1 };
-                (sout as u32, 0)
+    fn overflows(&self, input0: u32, input1: u32) -> bool {
+        let (a, b) = (input0 as i32, input1 as i32);
+        match self {
+            BinaryOperator::ADD | BinaryOperator::ADDI | BinaryOperator::SUB => {
+                self.signed_overflow(input0, input1)
             }
-            BinaryOperator::MUL => (input0.overflowing_mul(input1).0, 0),
-            BinaryOperator::SLTU => {
-                if input0 < input1 {
-                    (1, 0)
-                } else {
-                    (0, 0)
-                }
+            BinaryOperator::ADDIU => a.checked_add(sign_extend::<16>(input1) as i32).is_none(),
+            BinaryOperator::ADDU => input0.checked_add(input1).is_none(),
+            BinaryOperator::SUBU => input0.checked_sub(input1).is_none(),
+            BinaryOperator::MUL => a.checked_mul(b).is_none(),
+            BinaryOperator::DIV => b != 0 && a.checked_div(b).is_none(),
+            _ => false,
+        }
+    }
+
+    /// Whether the operation overflows as a signed 32-bit operation.
+    ///
+    /// Only ADD, ADDI and SUB trap on overflow in MIPS; `result` still
+    /// returns the wrapped value for them, and it is up to the caller
+    /// to raise the exception.
+    pub(crate) fn signed_overflow(&self, input0: u32, input1: u32) -> bool {
+        match self {
+            BinaryOperator::ADD => (input0 as i32).checked_add(input1 as i32).is_none(),
+            BinaryOperator::ADDI => {
+                let sein = sign_extend::<16>(input1);
+                (input0 as i32).checked_add(sein as i32).is_none()
             }
-            BinaryOperator::SLT => {
-                if (input0 as i32) < (input1 as i32) {
-                    (1, 0)
-                } else {
-                    (0, 0)
+            BinaryOperator::SUB => (input0 as i32).checked_sub(input1 as i32).is_none(),
+            _ => false,
+        }
+    }
+
+    /// Whether this is a shift by zero, which leaves `input0` unchanged
+    /// and is proven in a single `IS_SHIFT_ZERO` row; see `shift_zero`.
+    /// A shift by a nonzero multiple of 32 leaves `input0` unchanged as
+    /// well, but takes the rows of its shift, since the CPU lookup sees
+    /// the unmasked amount.
+    pub(crate) fn is_shift_by_zero(&self, input1: u32) -> bool {
+        self.is_simulated() && input1 == 0
+    }
+}
+
+/// The rows and operands that the `generate` of a `define_binops!`
+/// entry fills in, with `filter` already set in `row`.
+struct BinopRows<'a, F> {
+    row: &'a mut [F],
+    nv: &'a mut [F],
+    filter: usize,
+    input0: u32,
+    input1: u32,
+    result0: u32,
+    result1: u32,
+}
+
+/// Declare every binary operator with its filter, number of rows,
+/// result and row generation in one place. Each entry reads
+///
+///    NAME => filter IS_NAME, rows N,
+///        result |input0, input1| (lo, hi),
+///        generate |r| ...;
+///
+/// where `generate` fills in `r.row`, and `r.nv` if N is 2, from the
+/// `BinopRows` `r`. The macro emits `BinaryOperator::result`,
+/// `BinaryOperator::row_filter`, `BinaryOperator::num_rows` and the
+/// row generation of `binary_op_to_rows` as exhaustive matches, so an
+/// operator without an entry does not compile. The operator still needs
+/// its variant, its place in `BINARY_OPERATORS` and its filter column.
+macro_rules! define_binops {
+    ($($name:ident => filter $filter:ident, rows $rows:literal,
+        result |$a:ident, $b:ident| $result:expr,
+        generate |$r:ident| $generate:expr;)*) => {
+        impl BinaryOperator {
+            pub(crate) fn result(&self, input0: u32, input1: u32) -> (u32, u32) {
+                match self {
+                    $(BinaryOperator::$name => {
+                        let ($a, $b) = (input0, input1);
+                        $result
+                    })*
                 }
             }
-            BinaryOperator::SLTIU => {
-                let out = sign_extend::<16>(input1);
-                if input0 < out {
-                    (1, 0)
-                } else {
-                    (0, 0)
+
+            pub(crate) fn row_filter(&self) -> usize {
+                match self {
+                    $(BinaryOperator::$name => columns::$filter,)*
                 }
             }
-            BinaryOperator::SLTI => {
-                let out = sign_extend::<16>(input1);
-                if (input0 as i32) < (out as i32) {
-                    (1, 0)
-                } else {
-                    (0, 0)
+
+            /// The number of rows, except for a shift by zero, which
+            /// takes one; see `Operation::num_rows`.
+            fn num_rows(&self) -> usize {
+                match self {
+                    $(BinaryOperator::$name => $rows,)*
                 }
             }
-            BinaryOperator::LUI => {
-                let out = sign_extend::<16>(input0);
-                (out.overflowing_shl(16).0, 0)
+        }
+
+        fn generate_binop_rows<F: PrimeField64>(op: BinaryOperator, rows: BinopRows<F>) {
+            match op {
+                $(BinaryOperator::$name => {
+                    let $r = rows;
+                    $generate;
+                })*
             }
+        }
+    };
+}
 
-            BinaryOperator::MULT => {
-                let out = (((input0 as i32) as i64) * ((input1 as i32) as i64)) as u64;
-                (out as u32, (out >> 32) as u32) // lo,hi
+define_binops! {
+    ADD => filter IS_ADD, rows 1,
+        result |input0, input1| (input0.overflowing_add(input1).0, 0),
+        generate |r| addcy::generate(r.row, r.filter, r.input0, r.input1);
+    ADDU => filter IS_ADDU, rows 1,
+        result |input0, input1| (input0.overflowing_add(input1).0, 0),
+        generate |r| addcy::generate(r.row, r.filter, r.input0, r.input1);
+    ADDI => filter IS_ADDI, rows 1,
+        result |input0, input1| {
+            let sein = sign_extend::<16>(input1);
+            (input0.overflowing_add(sein).0, 0)
+        },
+        generate |r| addcy::generate(r.row, r.filter, r.input0, r.input1);
+    ADDIU => filter IS_ADDIU, rows 1,
+        result |input0, input1| {
+            let sein = sign_extend::<16>(input1);
+            (input0.overflowing_add(sein).0, 0)
+        },
+        generate |r| addcy::generate(r.row, r.filter, r.input0, r.input1);
+    SUB => filter IS_SUB, rows 1,
+        result |input0, input1| (input0.overflowing_sub(input1).0, 0),
+        generate |r| addcy::generate(r.row, r.filter, r.input0, r.input1);
+    SUBU => filter IS_SUBU, rows 1,
+        result |input0, input1| (input0.overflowing_sub(input1).0, 0),
+        generate |r| addcy::generate(r.row, r.filter, r.input0, r.input1);
+    MULT => filter IS_MULT, rows 1,
+        result |input0, input1| {
+            let out = (((input0 as i32) as i64) * ((input1 as i32) as i64)) as u64;
+            (out as u32, (out >> 32) as u32) // lo,hi
+        },
+        generate |r| mult::generate(r.row, r.filter, r.input0, r.input1);
+    MULTU => filter IS_MULTU, rows 1,
+        result |input0, input1| {
+            let out = input0 as u64 * input1 as u64;
+            (out as u32, (out >> 32) as u32) //lo,hi
+        },
+        generate |r| mult::generate(r.row, r.filter, r.input0, r.input1);
+    MUL => filter IS_MUL, rows 1,
+        result |input0, input1| (input0.overflowing_mul(input1).0, 0),
+        generate |r| mul::generate(r.row, r.input0, r.input1);
+    // MIPS leaves the result of a division by zero unpredictable; the
+    // STARK fixes it to quotient 0 and remainder `input0`.
+    // `i32::MIN / -1` wraps around to `i32::MIN` with remainder 0.
+    DIV => filter IS_DIV, rows 2,
+        result |input0, input1| {
+            if input1 == 0 {
+                (0, input0)
+            } else {
+                (
+                    (input0 as i32).wrapping_div(input1 as i32) as u32, // lo
+                    (input0 as i32).wrapping_rem(input1 as i32) as u32, // hi
+                )
             }
-            BinaryOperator::MULTU => {
-                let out = input0 as u64 * input1 as u64;
-                (out as u32, (out >> 32) as u32) //lo,hi
+        },
+        generate |r| {
+            div::generate(r.row, r.nv, r.filter, r.input0, r.input1, r.result0, r.result1)
+        };
+    DIVU => filter IS_DIVU, rows 2,
+        result |input0, input1| {
+            if input1 == 0 {
+                (0, input0)
+            } else {
+                (input0 / input1, input0 % input1) //lo,hi
             }
-            BinaryOperator::DIV => (
-                ((input0 as i32) / (input1 as i32)) as u32, // lo
-                ((input0 as i32) % (input1 as i32)) as u32, // hi
-            ),
-            BinaryOperator::DIVU => (input0 / input1, input0 % input1), //lo,hi
-            BinaryOperator::MFHI
-            | BinaryOperator::MTHI
-            | BinaryOperator::MFLO
-            | BinaryOperator::MTLO => (input0, 0),
-        }
-    }
+        },
+        generate |r| {
+            div::generate(r.row, r.nv, r.filter, r.input0, r.input1, r.result0, r.result1)
+        };
+    // The shift rows keep the amount as the CPU sends it and prove that
+    // only its low 5 bits are used.
+    SLLV => filter IS_SLLV, rows 1,
+        result |input0, input1| (input0 << mask_shift_amount(input1), 0),
+        generate |r| shift::generate(r.row, r.nv, r.filter, r.input1, r.input0, r.result0);
+    SRLV => filter IS_SRLV, rows 2,
+        result |input0, input1| (input0 >> mask_shift_amount(input1), 0),
+        generate |r| shift::generate(r.row, r.nv, r.filter, r.input1, r.input0, r.result0);
+    SRAV => filter IS_SRAV, rows 2,
+        result |input0, input1| (((input0 as i32) >> mask_shift_amount(input1)) as u32, 0),
+        generate |r| sra::generate(r.row, r.nv, r.filter, r.input1, r.input0, r.result0);
+    SLL => filter IS_SLL, rows 1,
+        result |input0, input1| (input0 << mask_shift_amount(input1), 0),
+        generate |r| shift::generate(r.row, r.nv, r.filter, r.input1, r.input0, r.result0);
+    SRL => filter IS_SRL, rows 2,
+        result |input0, input1| (input0 >> mask_shift_amount(input1), 0),
+        generate |r| shift::generate(r.row, r.nv, r.filter, r.input1, r.input0, r.result0);
+    SRA => filter IS_SRA, rows 2,
+        result |input0, input1| (((input0 as i32) >> mask_shift_amount(input1)) as u32, 0),
+        generate |r| sra::generate(r.row, r.nv, r.filter, r.input1, r.input0, r.result0);
+    SLT => filter IS_SLT, rows 1,
+        result |input0, input1| (((input0 as i32) < (input1 as i32)) as u32, 0),
+        generate |r| slt::generate(r.row, r.filter, r.input0, r.input1, r.result0);
+    SLTU => filter IS_SLTU, rows 1,
+        result |input0, input1| ((input0 < input1) as u32, 0),
+        generate |r| slt::generate(r.row, r.filter, r.input0, r.input1, r.result0);
+    SLTI => filter IS_SLTI, rows 1,
+        result |input0, input1| {
+            let out = sign_extend::<16>(input1);
+            (((input0 as i32) < (out as i32)) as u32, 0)
+        },
+        generate |r| slt::generate(r.row, r.filter, r.input0, r.input1, r.result0);
+    SLTIU => filter IS_SLTIU, rows 1,
+        result |input0, input1| {
+            let out = sign_extend::<16>(input1);
+            ((input0 < out) as u32, 0)
+        },
+        generate |r| slt::generate(r.row, r.filter, r.input0, r.input1, r.result0);
+    LUI => filter IS_LUI, rows 1,
+        result |input0, _input1| {
+            let out = sign_extend::<16>(input0);
+            (out.overflowing_shl(16).0, 0)
+        },
+        generate |r| lui::generate(r.row, r.nv, r.filter, r.input0, r.result0);
+    MFHI => filter IS_MFHI, rows 1,
+        result |input0, _input1| (input0, 0),
+        generate |r| lo_hi::generate(r.row, r.filter, r.input0, r.result0);
+    MTHI => filter IS_MTHI, rows 1,
+        result |input0, _input1| (input0, 0),
+        generate |r| lo_hi::generate(r.row, r.filter, r.input0, r.result0);
+    MFLO => filter IS_MFLO, rows 1,
+        result |input0, _input1| (input0, 0),
+        generate |r| lo_hi::generate(r.row, r.filter, r.input0, r.result0);
+    MTLO => filter IS_MTLO, rows 1,
+        result |input0, _input1| (input0, 0),
+        generate |r| lo_hi::generate(r.row, r.filter, r.input0, r.result0);
+    // Only the filter is set, and nothing is constrained.
+    NOOP => filter IS_NOOP, rows 1,
+        result |_input0, _input1| (0, 0),
+        generate |_r| ();
+}
 
-    pub(crate) fn row_filter(&self) -> usize {
-        match self {
-            BinaryOperator::ADD => columns::IS_ADD,
-            BinaryOperator::ADDU => columns::IS_ADDU,
-            BinaryOperator::ADDI => columns::IS_ADDI,
-            BinaryOperator::ADDIU => columns::IS_ADDIU,
-            BinaryOperator::SUB => columns::IS_SUB,
-            BinaryOperator::SUBU => columns::IS_SUBU,
-            BinaryOperator::MULT => columns::IS_MULT,
-            BinaryOperator::MULTU => columns::IS_MULTU,
-            BinaryOperator::MUL => columns::IS_MUL,
-            BinaryOperator::DIV => columns::IS_DIV,
-            BinaryOperator::DIVU => columns::IS_DIVU,
-            BinaryOperator::SLL => columns::IS_SLL,
-            BinaryOperator::SRL => columns::IS_SRL,
-            BinaryOperator::SRA => columns::IS_SRA,
-            BinaryOperator::SLLV => columns::IS_SLLV,
-            BinaryOperator::SRLV => columns::IS_SRLV,
-            BinaryOperator::SRAV => columns::IS_SRAV,
-            BinaryOperator::SLTIU => columns::IS_SLTIU,
-            BinaryOperator::SLTI => columns::IS_SLTI,
-            BinaryOperator::SLTU => columns::IS_SLTU,
-            BinaryOperator::SLT => columns::IS_SLT,
-            BinaryOperator::LUI => columns::IS_LUI,
-            BinaryOperator::MFHI => columns::IS_MFHI,
-            BinaryOperator::MTHI => columns::IS_MTHI,
-            BinaryOperator::MFLO => columns::IS_MFLO,
-            BinaryOperator::MTLO => columns::IS_MTLO,
-        }
-    }
+/// Inputs for which the result of an operation is not defined by MIPS
+/// and `BinaryOperator::result` returns a fixed value instead.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArithmeticError {
+    /// DIV or DIVU with a zero divisor.
+    DivByZero,
+}
+
+/// How `BinaryOperator::result_with_policy` treats inputs whose exact
+/// result does not fit in the output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Wrap modulo 2^32, as the MIPS instructions do.
+    Wrap,
+    /// Report overflow, for comparing against a checked reference model.
+    Checked,
+}
+
+/// Shifts only use the low 5 bits of their amount, so e.g. a shift by
+/// 32 is a shift by 0 and a shift by 34 is a shift by 2. For SLLV, SRLV
+/// and SRAV the amount is a register value. For SLL, SRL and SRA it is
+/// the 5-bit `sa` field, which masking leaves unchanged, but an amount
+/// that was not masked upstream still behaves as on MIPS hardware.
+///
+/// The shift rows keep the amount unmasked, as the CPU sends it, and
+/// their constraints show that only its low 5 bits are used (see
+/// `shift` and `sra`).
+pub(crate) fn mask_shift_amount(shift: u32) -> u32 {
+    shift & 0x1f
 }
 
 /// An enum representing arithmetic operations that can be either binary.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-rows", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum Operation {
     BinaryOperation {
         operator: BinaryOperator,
@@ -179,20 +463,21 @@ pub(crate) enum Operation {
 impl Operation {
     /// Create a binary operator with given inputs.
     ///
-    /// NB: This works as you would expect, EXCEPT for SHL and SHR,
-    /// whose inputs need a small amount of preprocessing. Specifically,
-    /// to create `SHL(shift, value)`, call (note the reversal of
-    /// argument order):
-    ///
-    ///    `Operation::binary(BinaryOperator::Shl, value, 1 << shift)`
-    ///
-    /// Similarly, to create `SHR(shift, value)`, call
+    /// A shift takes the value to shift as `input0` and the shift amount
+    /// as `input1`, the reverse of the register order of SLLV, SRLV and
+    /// SRAV (`rd = rt << rs`); `shift_left` and `shift_right` take their
+    /// arguments in that order for SLL and SRL. The shift amount may be
+    /// unmasked, e.g. the raw register value of SLLV; only its low 5
+    /// bits are used (see `mask_shift_amount`).
     ///
-    ///    `Operation::binary(BinaryOperator::Shr, value, 1 << shift)`
-    ///
-    /// See witness/operation.rs::append_shift() for an example (indeed
-    /// the only call site for such inputs).
+    /// With the `debug-arith` feature, inputs for which `result` falls
+    /// back to a fixed value (see `BinaryOperator::try_result`) are
+    /// logged as warnings.
     pub(crate) fn binary(operator: BinaryOperator, input0: u32, input1: u32) -> Self {
+        #[cfg(feature = "debug-arith")]
+        if let Err(err) = operator.try_result(input0, input1) {
+            log::warn!("{operator:?}({input0:#x}, {input1:#x}): {err:?}");
+        }
         let (result0, result1) = operator.result(input0, input1);
         Self::BinaryOperation {
             operator,
@@ -203,6 +488,25 @@ impl Operation {
         }
     }
 
+    /// Create `SLL(value, shift)`, shifting `value` left by the low 5
+    /// bits of `shift`.
+    pub(crate) fn shift_left(value: u32, shift: u32) -> Self {
+        Self::binary(BinaryOperator::SLL, value, mask_shift_amount(shift))
+    }
+
+    /// Create `SRL(value, shift)`, shifting `value` right by the low 5
+    /// bits of `shift`.
+    pub(crate) fn shift_right(value: u32, shift: u32) -> Self {
+        Self::binary(BinaryOperator::SRL, value, mask_shift_amount(shift))
+    }
+
+    /// Create `operator(input0, imm)` from the raw 16-bit immediate of
+    /// ADDI, ADDIU, SLTI or SLTIU, sign-extending it as the instruction
+    /// does.
+    pub(crate) fn binary_imm(operator: BinaryOperator, input0: u32, imm: u16) -> Self {
+        Self::binary(operator, input0, sign_extend_imm16(imm))
+    }
+
     pub(crate) fn result(&self) -> (u32, u32) {
         match self {
             Operation::BinaryOperation {
@@ -211,6 +515,52 @@ impl Operation {
         }
     }
 
+    /// The result, or the reason MIPS leaves it unpredictable; see
+    /// `BinaryOperator::try_result`.
+    pub(crate) fn try_result(&self) -> Result<(u32, u32), ArithmeticError> {
+        match self {
+            Operation::BinaryOperation {
+                operator,
+                input0,
+                input1,
+                ..
+            } => operator.try_result(*input0, *input1),
+        }
+    }
+
+    /// Whether the result goes to HI:LO rather than `rd`; see
+    /// `BinaryOperator::writes_hi_lo`.
+    pub fn writes_hi_lo(&self) -> bool {
+        match self {
+            Operation::BinaryOperation { operator, .. } => operator.writes_hi_lo(),
+        }
+    }
+
+    /// Whether the operation overflows and should raise an arithmetic
+    /// exception; see `BinaryOperator::signed_overflow`.
+    pub(crate) fn signed_overflow(&self) -> bool {
+        match self {
+            Operation::BinaryOperation {
+                operator,
+                input0,
+                input1,
+                ..
+            } => operator.signed_overflow(*input0, *input1),
+        }
+    }
+
+    /// The number of trace rows `to_rows` produces for this operation:
+    /// two for the operations whose constraints also read the next row,
+    /// one otherwise.
+    pub(crate) fn num_rows(&self) -> usize {
+        match self {
+            Operation::BinaryOperation {
+                operator, input1, ..
+            } if operator.is_shift_by_zero(*input1) => 1,
+            Operation::BinaryOperation { operator, .. } => operator.num_rows(),
+        }
+    }
+
     /// Convert operation into one or two rows of the trace.
     ///
     /// Morally these types should be [F; NUM_ARITH_COLUMNS], but we
@@ -221,93 +571,218 @@ impl Operation {
     /// The `is_simulated` bool indicates whether we use a native arithmetic
     /// operation or simulate one with another. This is used to distinguish
     /// SHL and SHR operations that are simulated through MUL and DIV respectively.
+    ///
+    /// The first row has exactly the filter of the operator set. The
+    /// second row, present iff `num_rows() == 2`, has no operation filter
+    /// set, because the constraints of the first row read it as `nv`; it
+    /// must directly follow the first row in the trace. Debug builds
+    /// check this in `debug_assert_row_order`.
     fn to_rows<F: PrimeField64>(&self) -> (Vec<F>, Option<Vec<F>>) {
-        match *self {
+        let mut row = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
+        let mut nv = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
+        if self.to_rows_into(&mut row, &mut nv) {
+            (row, Some(nv))
+        } else {
+            (row, None)
+        }
+    }
+
+    /// Like `to_rows`, but writes the rows into `row` and `next`, which
+    /// must have `NUM_ARITH_COLUMNS` entries each, so that callers can
+    /// reuse their buffers. Both are overwritten; returns whether `next`
+    /// holds a second row of the operation.
+    pub(crate) fn to_rows_into<F: PrimeField64>(&self, row: &mut [F], next: &mut [F]) -> bool {
+        debug_assert_eq!(row.len(), columns::NUM_ARITH_COLUMNS);
+        debug_assert_eq!(next.len(), columns::NUM_ARITH_COLUMNS);
+        row.fill(F::ZERO);
+        next.fill(F::ZERO);
+
+        let has_next = match *self {
             Operation::BinaryOperation {
                 operator,
                 input0,
                 input1,
                 result0,
                 result1,
-            } => binary_op_to_rows(operator, input0, input1, result0, result1),
+            } => binary_op_to_rows(operator, input0, input1, result0, result1, row, next),
+        };
+        operator_id::generate(row);
+
+        debug_assert_row_order(self, row, has_next.then_some(&*next));
+        has_next
+    }
+}
+
+/// Check the row contract of `Operation::to_rows`: the first row has
+/// only the filter of `op` set and the second row, present exactly for
+/// two-row operations, has no operation filter set. A continuation row
+/// can then never be mistaken for the start of an operation, and so
+/// never counts towards the CPU lookup a second time. Nor may the
+/// second row set `SHIFT_ZERO_FUNCT` or `OPERATOR_ID`, or the range
+/// check columns, which trace generation fills in: it only writes the
+/// shared columns and `AUX_EXTRA`.
+pub(crate) fn debug_assert_row_order<F: PrimeField64>(op: &Operation, row: &[F], nv: Option<&[F]>) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let filter = match op {
+        Operation::BinaryOperation {
+            operator, input1, ..
+        } if operator.is_shift_by_zero(*input1) => columns::IS_SHIFT_ZERO,
+        Operation::BinaryOperation { operator, .. } => operator.row_filter(),
+    };
+    debug_assert_eq!(
+        nv.is_some(),
+        op.num_rows() == 2,
+        "{op:?}: wrong number of rows"
+    );
+    for col in 0..columns::START_SHARED_COLS {
+        let expected = if col == filter { F::ONE } else { F::ZERO };
+        debug_assert!(
+            row[col] == expected,
+            "{op:?}: operation filter {col} of the first row is {}",
+            row[col]
+        );
+        if let Some(nv) = nv {
+            debug_assert!(
+                nv[col] == F::ZERO,
+                "{op:?}: operation filter {col} is set in the second row"
+            );
+        }
+    }
+    if let Some(nv) = nv {
+        let auxiliary =
+            |col: &usize| columns::SHARED_COLS.contains(col) || columns::AUX_EXTRA.contains(col);
+        for col in
+            (columns::START_SHARED_COLS..columns::NUM_ARITH_COLUMNS).filter(|c| !auxiliary(c))
+        {
+            debug_assert!(
+                nv[col] == F::ZERO,
+                "{op:?}: column {col} is set in the second row, which only uses the shared columns and AUX_EXTRA"
+            );
         }
     }
 }
 
+/// The number of rows `ops` occupy in the arithmetic trace before
+/// padding, so that callers can size the trace up front.
+pub(crate) fn estimated_rows(ops: &[Operation]) -> usize {
+    ops.iter().map(Operation::num_rows).sum()
+}
+
+/// Split `ops` into consecutive chunks of at most `max_rows` rows each,
+/// so that the arithmetic table of each chunk can be proven on its own.
+/// A two-row operation is never split across chunks. Chunks are filled
+/// greedily in order, so the same `ops` always give the same chunks.
+pub(crate) fn chunk_operations(ops: Vec<Operation>, max_rows: usize) -> Vec<Vec<Operation>> {
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut rows = 0;
+    for op in ops {
+        let op_rows = op.num_rows();
+        assert!(
+            op_rows <= max_rows,
+            "{op:?} takes {op_rows} rows, more than {max_rows}"
+        );
+        if rows + op_rows > max_rows {
+            chunks.push(core::mem::take(&mut chunk));
+            rows = 0;
+        }
+        rows += op_rows;
+        chunk.push(op);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// The share of the arithmetic table taken by one or more operations,
+/// before the trace is padded to a power of two of at least 2^16 rows.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RowCost {
+    /// Rows of the arithmetic trace.
+    pub rows: usize,
+    /// Range-check lookups. Every row looks up all `NUM_SHARED_COLS`
+    /// shared columns, whether the operation uses them or not.
+    pub range_checks: usize,
+}
+
+impl core::iter::Sum for RowCost {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, cost| Self {
+            rows: acc.rows + cost.rows,
+            range_checks: acc.range_checks + cost.range_checks,
+        })
+    }
+}
+
+/// The rows and range checks `op` adds to the arithmetic table.
+pub(crate) fn trace_cost(op: &Operation) -> RowCost {
+    let rows = op.num_rows();
+    RowCost {
+        rows,
+        range_checks: rows * columns::NUM_SHARED_COLS,
+    }
+}
+
 fn binary_op_to_rows<F: PrimeField64>(
     op: BinaryOperator,
     input0: u32,
     input1: u32,
     result0: u32,
     result1: u32,
-) -> (Vec<F>, Option<Vec<F>>) {
-    let mut row = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
-    row[op.row_filter()] = F::ONE;
-
-    match op {
-        BinaryOperator::ADD
-        | BinaryOperator::SUB
-        | BinaryOperator::ADDI
-        | BinaryOperator::ADDIU
-        | BinaryOperator::ADDU
-        | BinaryOperator::SUBU => {
-            addcy::generate(&mut row, op.row_filter(), input0, input1);
-            (row, None)
-        }
-        BinaryOperator::MUL => {
-            mul::generate(&mut row, input0, input1);
-            (row, None)
-        }
-        BinaryOperator::SLT
-        | BinaryOperator::SLTI
-        | BinaryOperator::SLTU
-        | BinaryOperator::SLTIU => {
-            slt::generate(&mut row, op.row_filter(), input0, input1, result0);
-            (row, None)
-        }
-        BinaryOperator::MULT | BinaryOperator::MULTU => {
-            mult::generate(&mut row, op.row_filter(), input0, input1);
-            (row, None)
-        }
-        BinaryOperator::DIV | BinaryOperator::DIVU => {
-            let mut nv = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
-            div::generate(
-                &mut row,
-                &mut nv,
-                op.row_filter(),
-                input0,
-                input1,
-                result0,
-                result1,
+    row: &mut [F],
+    nv: &mut [F],
+) -> bool {
+    if op.is_shift_by_zero(input1) {
+        row[columns::IS_SHIFT_ZERO] = F::ONE;
+        shift_zero::generate(row, op, input0);
+        debug_assert_limbs_in_range(op, input0, input1, row, None);
+        return false;
+    }
+
+    let filter = op.row_filter();
+    row[filter] = F::ONE;
+    generate_binop_rows(
+        op,
+        BinopRows {
+            row: &mut *row,
+            nv: &mut *nv,
+            filter,
+            input0,
+            input1,
+            result0,
+            result1,
+        },
+    );
+
+    let has_next = op.num_rows() == 2;
+    debug_assert_limbs_in_range(op, input0, input1, row, has_next.then_some(&*nv));
+    has_next
+}
+
+/// Check that the range-checked columns of the generated rows hold
+/// 16-bit limbs, so that a packing bug shows up here, with the
+/// operation that caused it, rather than when the range checks are
+/// generated for the whole trace. Compiled out in release builds.
+fn debug_assert_limbs_in_range<F: PrimeField64>(
+    op: BinaryOperator,
+    input0: u32,
+    input1: u32,
+    row: &[F],
+    nv: Option<&[F]>,
+) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    for (i, r) in core::iter::once(row).chain(nv).enumerate() {
+        for col in columns::SHARED_COLS {
+            let x = r[col].to_canonical_u64();
+            debug_assert!(
+                x < 1 << columns::LIMB_BITS,
+                "{op:?}({input0:#x}, {input1:#x}): column {col} of row {i} is {x:#x}, which is not a 16-bit limb"
             );
-            (row, Some(nv))
-        }
-        BinaryOperator::LUI => {
-            let mut nv = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
-            lui::generate(&mut row, &mut nv, op.row_filter(), input0, result0);
-            (row, None)
-        }
-        BinaryOperator::SLL | BinaryOperator::SLLV => {
-            let mut nv = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
-            shift::generate(&mut row, &mut nv, op.row_filter(), input1, input0, result0);
-            (row, None)
-        }
-        BinaryOperator::SRL | BinaryOperator::SRLV => {
-            let mut nv = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
-            shift::generate(&mut row, &mut nv, op.row_filter(), input1, input0, result0);
-            (row, Some(nv))
-        }
-        BinaryOperator::SRA | BinaryOperator::SRAV => {
-            let mut nv = vec![F::ZERO; columns::NUM_ARITH_COLUMNS];
-            sra::generate(&mut row, &mut nv, op.row_filter(), input1, input0, result0);
-            (row, Some(nv))
-        }
-        BinaryOperator::MFHI
-        | BinaryOperator::MTHI
-        | BinaryOperator::MFLO
-        | BinaryOperator::MTLO => {
-            lo_hi::generate(&mut row, op.row_filter(), input0, result0);
-            (row, None)
         }
     }
 }
@@ -38,6 +38,19 @@
 //!   h(x) = \sum_{i=0}^{N-1} output0[i] * x^i
 //!   l(x) = \sum_{i=0}^{N-1} output1[i] * x^i
 //!   s(x) = \sum_i^{2N-2} aux[i] * x^i
+//!
+//! For MULT, a(x) and b(x) are the inputs sign-extended to 2N limbs.
+//! The coefficients of s(x) are minus the carries of the long
+//! multiplication: writing p[i] for the coefficients of a(x) * b(x),
+//! r[i] for the limbs of [h,l] and c[i] for the carry out of limb i,
+//!
+//!    p[i] + c[i-1] = r[i] + β c[i]   (with c[-1] = 0),
+//!
+//! and aux[i] = -c[i]. Each aux[i] is stored offset by
+//! `AUX_COEFF_ABS_MAX`, split into a low 16-bit half in `MULT_AUX_LO`
+//! and a high half in `MULT_AUX_HI`. `carries` reads the c[i] back, so
+//! that a caller chaining MULT(U)s into a multi-word product does not
+//! have to redo the long multiplication.
 
 use plonky2::field::extension::Extendable;
 use plonky2::field::packed::PackedField;
@@ -153,6 +166,26 @@ pub(crate) fn generate_mult_helper<F: PrimeField64>(
     lv[MULT_AUX_HI].copy_from_slice(&aux_limbs.map(|c| F::from_canonical_u16((c >> 16) as u16)));
 }
 
+/// Like `generate`, but also returns the carries of the long
+/// multiplication, as `carries` reads them from the row.
+pub fn generate_with_carries<F: PrimeField64>(
+    lv: &mut [F],
+    filter: usize,
+    input0: u32,
+    input1: u32,
+) -> [i64; 2 * N_LIMBS] {
+    generate(lv, filter, input0, input1);
+    carries(lv)
+}
+
+/// The carries c[i] of the long multiplication of a MULT(U) row; see
+/// the module documentation.
+pub fn carries<F: PrimeField64>(lv: &[F]) -> [i64; 2 * N_LIMBS] {
+    let lo = read_value_i64_limbs::<{ 2 * N_LIMBS }, _>(lv, MULT_AUX_LO);
+    let hi = read_value_i64_limbs::<{ 2 * N_LIMBS }, _>(lv, MULT_AUX_HI);
+    core::array::from_fn(|i| AUX_COEFF_ABS_MAX - (lo[i] + (hi[i] << LIMB_BITS)))
+}
+
 pub fn eval_packed_generic<P: PackedField>(
     lv: &[P; NUM_ARITH_COLUMNS],
     yield_constr: &mut ConstraintConsumer<P>,
@@ -504,6 +537,7 @@ mod tests {
     use rand_chacha::ChaCha8Rng;
 
     use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
 
     const N_RND_TESTS: usize = 100000;
     const OPS: [usize; 2] = [IS_MULT, IS_MULTU];
@@ -570,4 +604,100 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn generate_eval_consistency_mult_edge_cases() {
+        type F = GoldilocksField;
+
+        // (op, input0, input1, lo, hi)
+        let cases = [
+            // 0xFFFFFFFF * 0xFFFFFFFF == 0xFFFFFFFE_00000001
+            (
+                IS_MULTU,
+                0xFFFFFFFFu32,
+                0xFFFFFFFFu32,
+                0x00000001u32,
+                0xFFFFFFFEu32,
+            ),
+            // -1 * -1 == 1
+            (IS_MULT, 0xFFFFFFFF, 0xFFFFFFFF, 0x00000001, 0x00000000),
+            // -1 * 1 == -1
+            (IS_MULT, 0xFFFFFFFF, 0x00000001, 0xFFFFFFFF, 0xFFFFFFFF),
+            // 0xFFFFFFFF * 1 == 0xFFFFFFFF
+            (IS_MULTU, 0xFFFFFFFF, 0x00000001, 0xFFFFFFFF, 0x00000000),
+        ];
+
+        for (op_filter, left_in, right_in, lo, hi) in cases {
+            let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+            lv[op_filter] = F::ONE;
+
+            generate(&mut lv, op_filter, left_in, right_in);
+
+            let mut expected_lo = [F::ZERO; N_LIMBS];
+            let mut expected_hi = [F::ZERO; N_LIMBS];
+            u32_to_array(&mut expected_lo, lo);
+            u32_to_array(&mut expected_hi, hi);
+            assert_eq!(&lv[OUTPUT_REGISTER_LO], &expected_lo[..]);
+            assert_eq!(&lv[OUTPUT_REGISTER_HI], &expected_hi[..]);
+
+            let mut constraint_consumer = test_constraint_consumer();
+            eval_packed_generic(&lv, &mut constraint_consumer);
+            for &acc in &constraint_consumer.constraint_accs {
+                assert_eq!(acc, GoldilocksField::ZERO);
+            }
+        }
+    }
+
+    #[test]
+    fn carries_chain_into_a_64_bit_multiply() {
+        type F = GoldilocksField;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        let words = |x: u64| [x as u32, (x >> 32) as u32];
+        let limbs = |x: u32| {
+            let mut limbs = [0; 2 * N_LIMBS];
+            limbs[..N_LIMBS]
+                .copy_from_slice(&pack_u32::<F>(x).map(|l| l.to_canonical_u64() as i64));
+            limbs
+        };
+
+        for _ in 0..1000 {
+            let (a, b) = (rng.gen::<u64>(), rng.gen::<u64>());
+
+            // The four 32x32-bit partial products, each in one MULTU row.
+            let mut product = 0u128;
+            for (i, &x) in words(a).iter().enumerate() {
+                for (j, &y) in words(b).iter().enumerate() {
+                    let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+                    lv[IS_MULTU] = F::ONE;
+                    let carries = generate_with_carries(&mut lv, IS_MULTU, x, y);
+                    assert_eq!(carries, super::carries(&lv));
+
+                    let lo = unpack_u32(read_value(&lv, OUTPUT_REGISTER_LO));
+                    let hi = unpack_u32(read_value(&lv, OUTPUT_REGISTER_HI));
+                    let mut result = [0; 2 * N_LIMBS];
+                    result[..N_LIMBS].copy_from_slice(&limbs(lo)[..N_LIMBS]);
+                    result[N_LIMBS..].copy_from_slice(&limbs(hi)[..N_LIMBS]);
+
+                    let mut carry = 0;
+                    for (k, p) in pol_mul_lo(limbs(x), limbs(y)).into_iter().enumerate() {
+                        assert_eq!(p + carry, result[k] + (carries[k] << LIMB_BITS));
+                        carry = carries[k];
+                    }
+                    // A MULTU product fits in 64 bits.
+                    assert_eq!(carry, 0);
+
+                    let mut constraint_consumer = test_constraint_consumer();
+                    eval_packed_generic(&lv, &mut constraint_consumer);
+                    for &acc in &constraint_consumer.constraint_accs {
+                        assert_eq!(acc, GoldilocksField::ZERO);
+                    }
+
+                    let partial = ((hi as u128) << 32) | lo as u128;
+                    product += partial << (32 * (i + j));
+                }
+            }
+            assert_eq!(product, a as u128 * b as u128);
+        }
+    }
 }
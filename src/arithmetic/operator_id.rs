@@ -0,0 +1,147 @@
+//! The `OPERATOR_ID` column, which holds the operator of a row as an
+//! integer, so that trace analysis can read one column instead of
+//! scanning the operation filters.
+//!
+//! The filters are one-hot, so the id is the linear combination
+//!
+//!    OPERATOR_ID = \sum_c w_c * lv[c]
+//!
+//! over the filter columns c, with w_c the `id` of the operator whose
+//! filter is c. It is zero in the padding rows and the second rows of
+//! two-row operations. The shifts by zero share `IS_SHIFT_ZERO`, so
+//! their rows hold `SHIFT_ZERO_ID`, and `SHIFT_ZERO_FUNCT` tells them
+//! apart.
+
+use once_cell::sync::Lazy;
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use super::{BinaryOperator, BINARY_OPERATORS};
+use crate::arithmetic::columns::*;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+
+/// The id of a shift by zero, after those of the operators.
+pub const SHIFT_ZERO_ID: u32 = BINARY_OPERATORS.len() as u32 + 1;
+
+/// Every filter column, as `(name, column, id)` in column order, with
+/// `name` the operator without the `IS_` of its filter. `FILTER_IDS`
+/// and `columns::dump_layout` are both read off this list.
+pub(crate) fn filters() -> Vec<(String, usize, u32)> {
+    let mut filters = BinaryOperator::all()
+        .iter()
+        .map(|op| (format!("{op:?}"), op.row_filter(), op.id()))
+        .chain([("SHIFT_ZERO".to_string(), IS_SHIFT_ZERO, SHIFT_ZERO_ID)])
+        .collect::<Vec<_>>();
+    filters.sort_by_key(|&(_, col, _)| col);
+    filters
+}
+
+/// The weight w_c of each filter column c.
+static FILTER_IDS: Lazy<[u32; START_SHARED_COLS]> = Lazy::new(|| {
+    let mut ids = [0; START_SHARED_COLS];
+    for (_, col, id) in filters() {
+        ids[col] = id;
+    }
+    ids
+});
+
+/// Set `OPERATOR_ID` from the filters of `lv`, once they are set.
+pub(crate) fn generate<F: PrimeField64>(lv: &mut [F]) {
+    debug_assert!(lv.len() == NUM_ARITH_COLUMNS);
+
+    lv[OPERATOR_ID] = FILTER_IDS
+        .iter()
+        .zip(&lv[..START_SHARED_COLS])
+        .map(|(&w, &filter)| filter * F::from_canonical_u32(w))
+        .sum();
+}
+
+pub(crate) fn eval_packed_generic<P: PackedField>(
+    lv: &[P; NUM_ARITH_COLUMNS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let id = FILTER_IDS
+        .iter()
+        .zip(&lv[..START_SHARED_COLS])
+        .map(|(&w, &filter)| filter * P::Scalar::from_canonical_u32(w))
+        .sum::<P>();
+    yield_constr.constraint(lv[OPERATOR_ID] - id);
+}
+
+pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let mut id = builder.zero_extension();
+    for (&w, &filter) in FILTER_IDS.iter().zip(&lv[..START_SHARED_COLS]) {
+        id = builder.mul_const_add_extension(F::from_canonical_u32(w), filter, id);
+    }
+    let t = builder.sub_extension(lv[OPERATOR_ID], id);
+    yield_constr.constraint(builder, t);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::arithmetic::Operation;
+
+    type F = GoldilocksField;
+
+    fn constraints_hold(lv: &[F; NUM_ARITH_COLUMNS]) -> bool {
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(lv, &mut constraint_consumer);
+        constraint_consumer
+            .constraint_accs
+            .iter()
+            .all(|&acc| acc == F::ZERO)
+    }
+
+    fn rows(op: &Operation) -> (Vec<F>, Option<Vec<F>>) {
+        let (lv, nv) = op.to_rows::<F>();
+        for row in core::iter::once(&lv).chain(&nv) {
+            assert!(constraints_hold(&row.clone().try_into().unwrap()), "{op:?}");
+        }
+        (lv, nv)
+    }
+
+    #[test]
+    fn every_filter_has_a_distinct_id() {
+        let mut ids = FILTER_IDS.to_vec();
+        assert!(ids.iter().all(|&id| id != 0));
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), START_SHARED_COLS);
+    }
+
+    #[test]
+    fn operator_id_is_the_discriminant_plus_one() {
+        for &operator in BinaryOperator::all() {
+            // A shift by a nonzero amount, so that it has its own filter.
+            let (lv, nv) = rows(&Operation::binary(operator, 3, 7));
+            assert_eq!(operator.id(), operator as u32 + 1);
+            assert_eq!(lv[OPERATOR_ID], F::from_canonical_u32(operator.id()));
+            assert!(nv.map_or(true, |nv| nv[OPERATOR_ID] == F::ZERO));
+        }
+
+        let (lv, _) = rows(&Operation::binary(BinaryOperator::SRA, 5, 0));
+        assert_eq!(lv[OPERATOR_ID], F::from_canonical_u32(SHIFT_ZERO_ID));
+    }
+
+    #[test]
+    fn wrong_id_is_rejected() {
+        assert!(constraints_hold(&[F::ZERO; NUM_ARITH_COLUMNS]));
+
+        let (lv, _) = Operation::binary(BinaryOperator::ADDU, 1, 2).to_rows::<F>();
+        let mut lv: [F; NUM_ARITH_COLUMNS] = lv.try_into().unwrap();
+        lv[OPERATOR_ID] = F::from_canonical_u32(BinaryOperator::ADD.id());
+        assert!(!constraints_hold(&lv));
+    }
+}
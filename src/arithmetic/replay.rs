@@ -0,0 +1,142 @@
+//! Reconstruction of the operations of an arithmetic trace, for
+//! post-mortem debugging of a failed proof: regenerate the rows of the
+//! recovered operations and diff them against the trace.
+//!
+//! The operator of a row is read off its `OPERATOR_ID` (see
+//! `api::operator_from_row`) and the inputs off its registers. The
+//! result is recomputed from the inputs, so a row with a wrong output
+//! comes back as the operation it should have been. Shift amounts come
+//! back as the CPU sent them, which is what the rows hold. Inputs that
+//! an operator ignores, such as the second input of MTHI or both inputs
+//! of NOOP, come back as zero. Either way, the recovered operations
+//! generate the same rows.
+
+use core::ops::Range;
+
+use plonky2::field::types::PrimeField64;
+
+use super::api::{operator_from_row, ArithOperator};
+use super::columns::*;
+use super::utils::{read_value, unpack_u32};
+use super::{BinaryOperator, Operation};
+
+/// The word in the register `reg` of `row`.
+fn read_u32<F: PrimeField64>(row: &[F], reg: Range<usize>) -> u32 {
+    unpack_u32(read_value::<N_LIMBS, _>(row, reg))
+}
+
+/// The operations of `trace`, given as rows of `NUM_ARITH_COLUMNS`
+/// values in trace order. Padding rows are skipped, and the second row
+/// of a two-row operation is read with its first.
+pub(crate) fn operations_from_trace<F: PrimeField64>(trace: &[Vec<F>]) -> Vec<Operation> {
+    let mut ops = vec![];
+    let mut i = 0;
+    while i < trace.len() {
+        let row = &trace[i];
+        debug_assert_eq!(row.len(), NUM_ARITH_COLUMNS);
+        let op = match operator_from_row(row) {
+            Some(ArithOperator::Binary(operator)) => binary_from_row(operator, row),
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        i += op.num_rows();
+        ops.push(op);
+    }
+    ops
+}
+
+fn binary_from_row<F: PrimeField64>(operator: BinaryOperator, row: &[F]) -> Operation {
+    let in0 = read_u32(row, INPUT_REGISTER_0);
+    let in1 = read_u32(row, INPUT_REGISTER_1);
+
+    // A shift by zero holds the shifted value in the second register.
+    if row[IS_SHIFT_ZERO] == F::ONE {
+        return Operation::binary(operator, in1, 0);
+    }
+    match operator {
+        // The shifts hold the amount in the first register and the
+        // shifted value in the second.
+        BinaryOperator::SLL
+        | BinaryOperator::SLLV
+        | BinaryOperator::SRL
+        | BinaryOperator::SRLV
+        | BinaryOperator::SRA
+        | BinaryOperator::SRAV => Operation::binary(operator, in1, in0),
+        // The second register holds the multiplier 2^16.
+        BinaryOperator::LUI => Operation::binary(operator, in0, 0),
+        _ => Operation::binary(operator, in0, in1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::util::transpose;
+
+    use super::*;
+    use crate::arithmetic::arithmetic_stark::ArithmeticStark;
+
+    type F = GoldilocksField;
+
+    fn mixed_batch() -> Vec<Operation> {
+        vec![
+            Operation::binary(BinaryOperator::ADDU, 1, 2),
+            Operation::binary(BinaryOperator::DIV, 0xffff_fff9, 2),
+            Operation::binary(BinaryOperator::SLLV, 0x8765_4321, 4),
+            Operation::binary(BinaryOperator::SRA, 0x8765_4321, 17),
+            Operation::binary(BinaryOperator::SRL, 0x8765_4321, 0),
+            Operation::binary(BinaryOperator::LUI, 0xabcd, 0),
+            Operation::binary(BinaryOperator::ADDI, 5, 0xffff_ffff),
+            Operation::binary(BinaryOperator::MULTU, u32::MAX, u32::MAX),
+            Operation::binary(BinaryOperator::NOOP, 0, 0),
+        ]
+    }
+
+    fn to_rows_of(ops: &[Operation]) -> Vec<Vec<F>> {
+        ops.iter()
+            .flat_map(|op| {
+                let (lv, nv) = op.to_rows::<F>();
+                core::iter::once(lv).chain(nv)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recovers_the_operations_of_their_rows() {
+        let ops = mixed_batch();
+        assert_eq!(operations_from_trace(&to_rows_of(&ops)), ops);
+    }
+
+    #[test]
+    fn recovered_operations_regenerate_the_rows() {
+        let ops: Vec<_> = BinaryOperator::all()
+            .iter()
+            .map(|&operator| Operation::binary(operator, 3, 7))
+            .collect();
+        let rows = to_rows_of(&ops);
+        let recovered = operations_from_trace(&rows);
+        assert_eq!(recovered.len(), ops.len());
+        assert_eq!(to_rows_of(&recovered), rows);
+    }
+
+    #[test]
+    fn skips_padding_rows() {
+        let ops = mixed_batch();
+        let stark = ArithmeticStark::<F, 2>::default();
+        let columns = stark.generate_trace(ops.clone());
+        let trace = transpose(&columns.into_iter().map(|c| c.values).collect::<Vec<_>>());
+        assert!(trace.len() > to_rows_of(&ops).len());
+        assert_eq!(operations_from_trace(&trace), ops);
+    }
+
+    #[test]
+    fn shift_amounts_come_back_unmasked() {
+        let shifts = vec![
+            Operation::binary(BinaryOperator::SLLV, 0x8765_4321, 36),
+            Operation::binary(BinaryOperator::SRAV, 0x8765_4321, 32),
+        ];
+        assert_eq!(operations_from_trace(&to_rows_of(&shifts)), shifts);
+    }
+}
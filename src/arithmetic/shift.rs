@@ -19,23 +19,55 @@
 //!
 //! where β = 2^16 = 2^LIMB_BITS. To verify that A, S, B and C satisfy
 //! the equations, we proceed similarly to MUL for SLL(V) and to DIV for SRL(V).
+//!
+//! B itself is constrained to be 1 << (S mod 32), since MIPS only uses
+//! the low 5 bits of the shift amount. S is decomposed into bits
+//! s[0..5] and a quotient k with S = s[0] + 2 s[1] + ... + 16 s[4] + 32 k,
+//! where k is in two range-checked limbs. The low limb of 2^(S mod 16)
+//! is built up as the product of the (1 + (2^(2^i) - 1) s[i]) for i < 4,
+//! one factor per column so the degree stays at 3; s[4] then picks the
+//! limb of B it goes into.
+
+use core::ops::Range;
 
+use once_cell::sync::Lazy;
 use plonky2::field::extension::Extendable;
 use plonky2::field::packed::PackedField;
-use plonky2::field::types::PrimeField64;
+use plonky2::field::types::{Field, PrimeField64};
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 
-use super::{div, mul};
+use super::{div, mask_shift_amount, mul};
 use crate::arithmetic::columns::*;
 use crate::arithmetic::utils::{read_value, read_value_i64_limbs, u32_to_array};
 // use crate::arithmetic::utils::*;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 
+/// The quotient k of S = (S mod 32) + 32 k.
+const SHIFT_QUOTIENT: Range<usize> = AUX_INPUT_REGISTER_2;
+/// 2^(s[0] + 2 s[1]) and 2^(s[0] + 2 s[1] + 4 s[2]).
+const SHIFT_POW_PARTIAL: Range<usize> = SHIFT_QUOTIENT.end..SHIFT_QUOTIENT.end + 2;
+/// 2^(S mod 16).
+const SHIFT_POW_LIMB: usize = SHIFT_POW_PARTIAL.end;
+
+// The following are not range checked.
+/// The low 5 bits s[i] of S.
+const SHIFT_BITS: Range<usize> = AUX_EXTRA.start..AUX_EXTRA.start + 5;
+
+const _: () = {
+    check_columns(SHIFT_QUOTIENT.start..SHIFT_POW_LIMB + 1, SHARED_COLS);
+    check_columns(SHIFT_BITS, AUX_EXTRA);
+};
+
+/// `POWERS_OF_TWO[i] = 1 << i`, so that generating a shift row looks
+/// up its powers of two instead of computing them.
+static POWERS_OF_TWO: Lazy<[u32; 32]> = Lazy::new(|| core::array::from_fn(|i| 1 << i));
+
 /// Generates a shift operation (either SLL(V) or SRL(V)).
-/// The inputs are stored in the form `(shift, input, 1 << shift)`.
-/// NB: if `shift >= 32`, then the third register holds 0.
+/// The inputs are stored in the form `(shift, input, 1 << (shift mod 32))`.
+/// `shift` is the amount as the CPU sends it, e.g. the whole register
+/// of SLLV; only its low 5 bits are used.
 /// We leverage the functions in mul.rs and div.rs to carry out
 /// the computation.
 pub fn generate<F: PrimeField64>(
@@ -54,11 +86,11 @@ pub fn generate<F: PrimeField64>(
     // The second register holds the input which needs shifting.
     u32_to_array(&mut lv[INPUT_REGISTER_1], input);
     u32_to_array(&mut lv[OUTPUT_REGISTER], result);
-    // If `shift >= 32`, the shifted displacement is set to 0.
-    // Compute 1 << shift and store it in the third input register.
-    let shifted_displacement = if shift > 31 { 0 } else { 1 << shift };
+    // Compute 1 << (shift mod 32) and store it in the third input register.
+    let shifted_displacement = POWERS_OF_TWO[mask_shift_amount(shift) as usize];
 
     u32_to_array(&mut lv[INPUT_REGISTER_2], shifted_displacement);
+    generate_shifted_displacement(lv, shift);
 
     let input0 = read_value_i64_limbs(lv, INPUT_REGISTER_1); // input
     let input1 = read_value_i64_limbs(lv, INPUT_REGISTER_2); // 1 << shift
@@ -69,8 +101,8 @@ pub fn generate<F: PrimeField64>(
             mul::generate_mul(lv, input0, input1);
         }
         IS_SRL | IS_SRLV => {
-            // If the operation is IS_SRL(IS_SRLV), we compute: `input / shifted_displacement` if `shifted_displacement == 0`
-            // otherwise, the output is 0. We use the logic in div.rs to achieve that.
+            // If the operation is IS_SRL(IS_SRLV), we compute `input / shifted_displacement`.
+            // We use the logic in div.rs to achieve that.
             div::generate_divu_helper(
                 lv,
                 nv,
@@ -85,6 +117,133 @@ pub fn generate<F: PrimeField64>(
     }
 }
 
+/// Fill in the columns showing that INPUT_REGISTER_2 holds
+/// `1 << (shift mod 32)`.
+fn generate_shifted_displacement<F: PrimeField64>(lv: &mut [F], shift: u32) {
+    let bits = mask_shift_amount(shift);
+    for i in 0..5 {
+        lv[SHIFT_BITS.start + i] = F::from_canonical_u32((bits >> i) & 1);
+    }
+    u32_to_array(&mut lv[SHIFT_QUOTIENT], shift >> 5);
+
+    let pow = |n: u32| F::from_canonical_u32(POWERS_OF_TWO[n as usize]);
+    lv[SHIFT_POW_PARTIAL.start] = pow(bits & 0x3);
+    lv[SHIFT_POW_PARTIAL.start + 1] = pow(bits & 0x7);
+    lv[SHIFT_POW_LIMB] = pow(bits & 0xf);
+}
+
+/// Constrains INPUT_REGISTER_2 to hold 1 << (S mod 32), where S is the
+/// limbs of INPUT_REGISTER_0.
+fn eval_packed_shifted_displacement<P: PackedField>(
+    lv: &[P; NUM_ARITH_COLUMNS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv[IS_SLL] + lv[IS_SLLV] + lv[IS_SRL] + lv[IS_SRLV];
+    let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+
+    let bits: [P; 5] = read_value(lv, SHIFT_BITS);
+    for b in bits {
+        yield_constr.constraint(filter * b * (b - P::ONES));
+    }
+
+    // S < 2^32 and k is range checked, so the sum can't wrap around
+    // and the bits are the low 5 bits of S.
+    let shift = lv[INPUT_REGISTER_0.start] + lv[INPUT_REGISTER_0.start + 1] * base;
+    let low_bits = bits
+        .iter()
+        .rev()
+        .fold(P::ZEROS, |acc, &b| acc * P::Scalar::TWO + b);
+    let quotient = lv[SHIFT_QUOTIENT.start] + lv[SHIFT_QUOTIENT.start + 1] * base;
+    yield_constr
+        .constraint(filter * (shift - low_bits - quotient * P::Scalar::from_canonical_u64(32)));
+
+    // pow_i = pow_{i-1} * (1 + (2^(2^i) - 1) * s[i])
+    let pows = [
+        lv[SHIFT_POW_PARTIAL.start],
+        lv[SHIFT_POW_PARTIAL.start + 1],
+        lv[SHIFT_POW_LIMB],
+    ];
+    let mut prev = bits[0] + P::ONES;
+    for (i, pow) in pows.into_iter().enumerate() {
+        let factor =
+            bits[i + 1] * P::Scalar::from_canonical_u64((1 << (1 << (i + 1))) - 1) + P::ONES;
+        yield_constr.constraint(filter * (pow - prev * factor));
+        prev = pow;
+    }
+
+    // s[4] picks the limb of B that 2^(S mod 16) goes in.
+    let pow = lv[SHIFT_POW_LIMB];
+    yield_constr.constraint(filter * (lv[INPUT_REGISTER_2.start] - (P::ONES - bits[4]) * pow));
+    yield_constr.constraint(filter * (lv[INPUT_REGISTER_2.start + 1] - bits[4] * pow));
+}
+
+fn eval_ext_circuit_shifted_displacement<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = builder.add_many_extension([lv[IS_SLL], lv[IS_SLLV], lv[IS_SRL], lv[IS_SRLV]]);
+    let base = F::from_canonical_u64(1 << LIMB_BITS);
+    let one = builder.one_extension();
+
+    let bits: [ExtensionTarget<D>; 5] = read_value(lv, SHIFT_BITS);
+    for b in bits {
+        let t0 = builder.mul_sub_extension(b, b, b);
+        let t = builder.mul_extension(filter, t0);
+        yield_constr.constraint(builder, t);
+    }
+
+    let shift = builder.mul_const_add_extension(
+        base,
+        lv[INPUT_REGISTER_0.start + 1],
+        lv[INPUT_REGISTER_0.start],
+    );
+    let mut low_bits = builder.zero_extension();
+    for &b in bits.iter().rev() {
+        low_bits = builder.mul_const_add_extension(F::TWO, low_bits, b);
+    }
+    let quotient = builder.mul_const_add_extension(
+        base,
+        lv[SHIFT_QUOTIENT.start + 1],
+        lv[SHIFT_QUOTIENT.start],
+    );
+    // shift - low_bits - 32 * quotient
+    let t0 = builder.sub_extension(shift, low_bits);
+    let t1 = builder.mul_const_extension(F::from_canonical_u64(32), quotient);
+    let t2 = builder.sub_extension(t0, t1);
+    let t = builder.mul_extension(filter, t2);
+    yield_constr.constraint(builder, t);
+
+    let pows = [
+        lv[SHIFT_POW_PARTIAL.start],
+        lv[SHIFT_POW_PARTIAL.start + 1],
+        lv[SHIFT_POW_LIMB],
+    ];
+    let mut prev = builder.add_const_extension(bits[0], F::ONE);
+    for (i, pow) in pows.into_iter().enumerate() {
+        let c = F::from_canonical_u64((1 << (1 << (i + 1))) - 1);
+        let t0 = builder.mul_const_extension(c, bits[i + 1]);
+        let factor = builder.add_const_extension(t0, F::ONE);
+        let t1 = builder.mul_extension(prev, factor);
+        let t2 = builder.sub_extension(pow, t1);
+        let t = builder.mul_extension(filter, t2);
+        yield_constr.constraint(builder, t);
+        prev = pow;
+    }
+
+    let pow = lv[SHIFT_POW_LIMB];
+    let not_b4 = builder.sub_extension(one, bits[4]);
+    for (limb, sel) in [
+        (lv[INPUT_REGISTER_2.start], not_b4),
+        (lv[INPUT_REGISTER_2.start + 1], bits[4]),
+    ] {
+        let t0 = builder.mul_extension(sel, pow);
+        let t1 = builder.sub_extension(limb, t0);
+        let t = builder.mul_extension(filter, t1);
+        yield_constr.constraint(builder, t);
+    }
+}
+
 /// Evaluates the constraints for an SLL(V) opcode.
 /// The logic is the same as the one for MUL. The only difference is that
 /// the inputs are in `INPUT_REGISTER_1`  and `INPUT_REGISTER_2` instead of
@@ -131,6 +290,7 @@ pub fn eval_packed_generic<P: PackedField>(
 ) {
     eval_packed_sll(lv, yield_constr);
     eval_packed_srl(lv, nv, yield_constr);
+    eval_packed_shifted_displacement(lv, yield_constr);
 }
 
 fn eval_ext_circuit_sll<F: RichField + Extendable<D>, const D: usize>(
@@ -183,11 +343,14 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
 ) {
     eval_ext_circuit_sll(builder, lv, yield_constr);
     eval_ext_circuit_srl(builder, lv, nv, yield_constr);
+    eval_ext_circuit_shifted_displacement(builder, lv, yield_constr);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::arithmetic::utils::unpack_u32;
     use plonky2::field::goldilocks_field::GoldilocksField;
     use plonky2::field::types::{Field, Sample};
     use rand::{Rng, SeedableRng};
@@ -240,12 +403,11 @@ mod tests {
         for _i in 0..N_RND_TESTS {
             let shift: u32 = rng.gen_range(0..32);
 
-            let mut full_input = 0;
             // set inputs to random values
             for ai in INPUT_REGISTER_1 {
                 lv[ai] = F::from_canonical_u16(rng.gen());
-                full_input = lv[ai].to_canonical_u64() as u32 + full_input * (1 << 16);
             }
+            let full_input = unpack_u32(read_value(&lv, INPUT_REGISTER_1));
 
             let output = if filter == IS_SLL || filter == IS_SLLV {
                 full_input << shift
@@ -294,14 +456,25 @@ mod tests {
 
         for _i in 0..N_RND_TESTS {
             let shift: u32 = rng.gen_range(32..=u32::MAX);
-            let mut full_input = 0;
             // set inputs to random values
             for ai in INPUT_REGISTER_1 {
                 lv[ai] = F::from_canonical_u16(rng.gen());
-                full_input = lv[ai].to_canonical_u64() as u32 + full_input * (1 << 16);
             }
+            let full_input = unpack_u32(read_value(&lv, INPUT_REGISTER_1));
+
+            // Only the low 5 bits of the amount are used.
+            let output = if filter == IS_SLL || filter == IS_SLLV {
+                full_input << (shift % 32)
+            } else {
+                full_input >> (shift % 32)
+            };
 
-            generate(&mut lv, &mut nv, filter, shift, full_input, 0);
+            generate(&mut lv, &mut nv, filter, shift, full_input, output);
+            assert_eq!(
+                unpack_u32(read_value(&lv, INPUT_REGISTER_0)),
+                shift,
+                "the row keeps the amount unmasked"
+            );
 
             let mut constraint_consumer = ConstraintConsumer::new(
                 vec![GoldilocksField(2), GoldilocksField(3), GoldilocksField(5)],
@@ -323,4 +496,114 @@ mod tests {
         generate_eval_consistency_shift_over_32(IS_SRL);
         generate_eval_consistency_shift_over_32(IS_SRLV);
     }
+
+    /// Generate `filter(shift, input)` with `displacement` in place of
+    /// `1 << shift`, keeping the MUL/DIV part of the row consistent with
+    /// it, and check whether the constraints hold.
+    fn constraints_hold_with_displacement(
+        filter: usize,
+        shift: u32,
+        input: u32,
+        displacement: u32,
+    ) -> bool {
+        type F = GoldilocksField;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        let mut lv = [F::default(); NUM_ARITH_COLUMNS].map(|_| F::sample(&mut rng));
+        let mut nv = [F::default(); NUM_ARITH_COLUMNS].map(|_| F::sample(&mut rng));
+        [IS_SLL, IS_SLLV, IS_SRL, IS_SRLV, IS_DIV, IS_DIVU].map(|filter| lv[filter] = F::ZERO);
+        lv[filter] = F::ONE;
+
+        let result = if filter == IS_SLL || filter == IS_SLLV {
+            input << (shift % 32)
+        } else {
+            input >> (shift % 32)
+        };
+        generate(&mut lv, &mut nv, filter, shift, input, result);
+        u32_to_array(&mut lv[INPUT_REGISTER_2], displacement);
+        let input0 = read_value_i64_limbs(&lv, INPUT_REGISTER_1);
+        let input1 = read_value_i64_limbs(&lv, INPUT_REGISTER_2);
+        if filter == IS_SLL || filter == IS_SLLV {
+            mul::generate_mul(&mut lv, input0, input1);
+        } else {
+            let quotient = input.checked_div(displacement).unwrap_or(0);
+            u32_to_array(&mut lv[OUTPUT_REGISTER], quotient);
+            div::generate_divu_helper(
+                &mut lv,
+                &mut nv,
+                filter,
+                INPUT_REGISTER_1,
+                INPUT_REGISTER_2,
+                OUTPUT_REGISTER,
+                None,
+            );
+        }
+
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(&lv, &nv, &mut constraint_consumer);
+        constraint_consumer
+            .constraint_accs
+            .iter()
+            .all(|&acc| acc == GoldilocksField::ZERO)
+    }
+
+    #[test]
+    fn displacement_must_be_power_of_two() {
+        let input = 0x8765_4321;
+        for filter in [IS_SLL, IS_SLLV, IS_SRL, IS_SRLV] {
+            for shift in [0, 3, 15, 16, 31, 32, 40, 0xffff_ffff] {
+                // Shifting by 32 or more shifts by the low 5 bits only,
+                // e.g. by 40 needs the displacement 1 << 8.
+                let low = shift % 32;
+                assert!(
+                    constraints_hold_with_displacement(filter, shift, input, 1 << low),
+                    "filter {filter}, shift {shift}"
+                );
+                // Not a power of two, or the wrong one.
+                for displacement in [0, 6, (1 << low) + 1, 1 << ((low + 1) % 32)] {
+                    assert!(
+                        !constraints_hold_with_displacement(filter, shift, input, displacement),
+                        "filter {filter}, shift {shift}, displacement {displacement}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quotient_must_match_amount() {
+        type F = GoldilocksField;
+
+        for filter in [IS_SLL, IS_SLLV, IS_SRL, IS_SRLV] {
+            let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+            let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+            lv[filter] = F::ONE;
+            let (shift, input) = (40, 0x8765_4321u32);
+            let result = if filter == IS_SLL || filter == IS_SLLV {
+                input << 8
+            } else {
+                input >> 8
+            };
+            generate(&mut lv, &mut nv, filter, shift, input, result);
+            // Claim 40 = 8 + 32 * 2.
+            lv[SHIFT_QUOTIENT.start] += F::ONE;
+
+            let mut constraint_consumer = test_constraint_consumer();
+            eval_packed_generic(&lv, &nv, &mut constraint_consumer);
+            assert!(
+                constraint_consumer
+                    .constraint_accs
+                    .iter()
+                    .any(|&acc| acc != GoldilocksField::ZERO),
+                "filter {filter}"
+            );
+        }
+    }
+
+    #[test]
+    fn powers_of_two_table() {
+        for (i, &pow) in POWERS_OF_TWO.iter().enumerate() {
+            assert_eq!(pow, 1u32 << i);
+        }
+    }
 }
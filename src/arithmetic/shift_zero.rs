@@ -0,0 +1,252 @@
+//! Support for shifts by zero.
+//!
+//! SLL(V), SRL(V) and SRA(V) by a shift amount S are proven as a
+//! multiplication or division by 1 << S (see `shift` and `sra`), which
+//! takes two rows for the right shifts. When S is zero the shift is
+//! the identity, so it is proven in a single `IS_SHIFT_ZERO` row
+//! instead:
+//!
+//!    S = 0  and  C = A,
+//!
+//! with S in the first input register, as for the other shifts, A in
+//! the second and C in the output. A shift by a nonzero multiple of 32
+//! is the identity too (see `mask_shift_amount`), but the CPU lookup
+//! sees S itself, which this row cannot hold, so it takes the rows of
+//! its shift.
+//!
+//! All six shifts share the one filter, so the CPU lookup cannot tell
+//! them apart by filter alone. The row therefore also holds the MIPS
+//! funct field of the shift, f = 4 b_2 + 2 b_1 + b_0, in
+//! `SHIFT_ZERO_FUNCT`, which is zero in every other row. The bits are
+//! boolean and b_0 = 1 implies b_1 = 1, which leaves exactly the funct
+//! fields of the six shifts:
+//!
+//!    SLL 0, SRL 2, SRA 3, SLLV 4, SRLV 6, SRAV 7.
+
+use core::ops::Range;
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::arithmetic::columns::*;
+use crate::arithmetic::utils::u32_to_array;
+use crate::arithmetic::BinaryOperator;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+
+/// The bits b_0, b_1, b_2 of the funct field.
+const SHIFT_ZERO_BITS: Range<usize> = AUX_INPUT_REGISTER_0.start..AUX_INPUT_REGISTER_0.start + 3;
+
+const _: () = check_columns(SHIFT_ZERO_BITS, SHARED_COLS);
+
+/// The MIPS funct field of the shift `op`.
+pub(crate) fn funct(op: BinaryOperator) -> u32 {
+    match op {
+        BinaryOperator::SLL => 0b000000,
+        BinaryOperator::SRL => 0b000010,
+        BinaryOperator::SRA => 0b000011,
+        BinaryOperator::SLLV => 0b000100,
+        BinaryOperator::SRLV => 0b000110,
+        BinaryOperator::SRAV => 0b000111,
+        _ => panic!("{op:?} is not a shift"),
+    }
+}
+
+/// Generate the row of `op(input, 0) = input`.
+pub(crate) fn generate<F: PrimeField64>(lv: &mut [F], op: BinaryOperator, input: u32) {
+    debug_assert!(lv.len() == NUM_ARITH_COLUMNS);
+
+    let funct = funct(op);
+    u32_to_array(&mut lv[INPUT_REGISTER_0], 0);
+    u32_to_array(&mut lv[INPUT_REGISTER_1], input);
+    u32_to_array(&mut lv[OUTPUT_REGISTER], input);
+    for (k, i) in SHIFT_ZERO_BITS.enumerate() {
+        lv[i] = F::from_canonical_u32((funct >> k) & 1);
+    }
+    lv[SHIFT_ZERO_FUNCT] = F::from_canonical_u32(funct);
+}
+
+pub(crate) fn eval_packed_generic<P: PackedField>(
+    lv: &[P; NUM_ARITH_COLUMNS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv[IS_SHIFT_ZERO];
+    let shift = &lv[INPUT_REGISTER_0];
+    let input = &lv[INPUT_REGISTER_1];
+    let output = &lv[OUTPUT_REGISTER];
+
+    for &s in shift {
+        yield_constr.constraint(filter * s);
+    }
+    for (&x, &c) in input.iter().zip(output) {
+        yield_constr.constraint(filter * (c - x));
+    }
+
+    let bits: [P; 3] = core::array::from_fn(|k| lv[SHIFT_ZERO_BITS.start + k]);
+    let mut funct = P::ZEROS;
+    for (k, &b) in bits.iter().enumerate() {
+        yield_constr.constraint(filter * b * (P::ONES - b));
+        funct += b * P::Scalar::from_canonical_u64(1 << k);
+    }
+    yield_constr.constraint(filter * bits[0] * (P::ONES - bits[1]));
+    yield_constr.constraint(filter * (lv[SHIFT_ZERO_FUNCT] - funct));
+    yield_constr.constraint((P::ONES - filter) * lv[SHIFT_ZERO_FUNCT]);
+}
+
+pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv[IS_SHIFT_ZERO];
+    let shift = &lv[INPUT_REGISTER_0];
+    let input = &lv[INPUT_REGISTER_1];
+    let output = &lv[OUTPUT_REGISTER];
+    let one = builder.one_extension();
+
+    for &s in shift {
+        let t = builder.mul_extension(filter, s);
+        yield_constr.constraint(builder, t);
+    }
+    for (&x, &c) in input.iter().zip(output) {
+        let t0 = builder.sub_extension(c, x);
+        let t = builder.mul_extension(filter, t0);
+        yield_constr.constraint(builder, t);
+    }
+
+    let bits: [ExtensionTarget<D>; 3] = core::array::from_fn(|k| lv[SHIFT_ZERO_BITS.start + k]);
+    let mut funct = builder.zero_extension();
+    for (k, &b) in bits.iter().enumerate() {
+        let t0 = builder.sub_extension(one, b);
+        let t = builder.mul_many_extension([filter, b, t0]);
+        yield_constr.constraint(builder, t);
+        funct = builder.mul_const_add_extension(F::from_canonical_u64(1 << k), b, funct);
+    }
+    let t0 = builder.sub_extension(one, bits[1]);
+    let t = builder.mul_many_extension([filter, bits[0], t0]);
+    yield_constr.constraint(builder, t);
+    let t0 = builder.sub_extension(lv[SHIFT_ZERO_FUNCT], funct);
+    let t = builder.mul_extension(filter, t0);
+    yield_constr.constraint(builder, t);
+    let t0 = builder.sub_extension(one, filter);
+    let t = builder.mul_extension(t0, lv[SHIFT_ZERO_FUNCT]);
+    yield_constr.constraint(builder, t);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Sample;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::arithmetic::Operation;
+
+    const N_RND_TESTS: usize = 1000;
+
+    const SHIFTS: [BinaryOperator; 6] = [
+        BinaryOperator::SLL,
+        BinaryOperator::SRL,
+        BinaryOperator::SRA,
+        BinaryOperator::SLLV,
+        BinaryOperator::SRLV,
+        BinaryOperator::SRAV,
+    ];
+
+    type F = GoldilocksField;
+
+    fn constraints_hold(lv: &[F; NUM_ARITH_COLUMNS]) -> bool {
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(lv, &mut constraint_consumer);
+        constraint_consumer
+            .constraint_accs
+            .iter()
+            .all(|&acc| acc == F::ZERO)
+    }
+
+    #[test]
+    fn generate_eval_consistency_not_shift_zero() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        let mut lv = [F::default(); NUM_ARITH_COLUMNS].map(|_| F::sample(&mut rng));
+
+        // if `IS_SHIFT_ZERO == 0`, then the constraints should be met
+        // even if all values but the funct column are garbage.
+        lv[IS_SHIFT_ZERO] = F::ZERO;
+        lv[SHIFT_ZERO_FUNCT] = F::ZERO;
+        assert!(constraints_hold(&lv));
+
+        lv[SHIFT_ZERO_FUNCT] = F::TWO;
+        assert!(!constraints_hold(&lv));
+    }
+
+    #[test]
+    fn generate_eval_consistency() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+        for op in SHIFTS {
+            for _ in 0..N_RND_TESTS {
+                let input = rng.gen::<u32>();
+                let (lv, nv) = Operation::binary(op, input, 0).to_rows::<F>();
+                assert_eq!(lv[IS_SHIFT_ZERO], F::ONE, "{op:?}({input:#x}, 0)");
+                assert!(nv.is_none());
+                assert_eq!(lv[SHIFT_ZERO_FUNCT], F::from_canonical_u32(funct(op)));
+                let lv: [F; NUM_ARITH_COLUMNS] = lv.try_into().unwrap();
+                assert!(constraints_hold(&lv), "{op:?}({input:#x}, 0)");
+            }
+        }
+    }
+
+    #[test]
+    fn multiples_of_32_take_the_shift_rows() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+        for op in SHIFTS {
+            let input = rng.gen::<u32>();
+            let shift = 32 * rng.gen_range(1..1 << 27);
+            let operation = Operation::binary(op, input, shift);
+            assert_eq!(operation.result(), (input, 0));
+            let (lv, _) = operation.to_rows::<F>();
+            assert_eq!(lv[IS_SHIFT_ZERO], F::ZERO, "{op:?}({input:#x}, {shift})");
+            assert_eq!(lv[op.row_filter()], F::ONE, "{op:?}({input:#x}, {shift})");
+            let amount = [shift & 0xffff, shift >> 16].map(F::from_canonical_u32);
+            assert_eq!(lv[INPUT_REGISTER_0], amount);
+        }
+    }
+
+    #[test]
+    fn wrong_rows_are_rejected() {
+        let (lv, _) = Operation::binary(BinaryOperator::SRL, 0x1234_5678, 0).to_rows::<F>();
+        let lv: [F; NUM_ARITH_COLUMNS] = lv.try_into().unwrap();
+        assert!(constraints_hold(&lv));
+
+        // A nonzero shift amount.
+        let mut bad = lv;
+        bad[INPUT_REGISTER_0.start] = F::ONE;
+        assert!(!constraints_hold(&bad));
+
+        // An output other than the input.
+        let mut bad = lv;
+        bad[OUTPUT_REGISTER.start] += F::ONE;
+        assert!(!constraints_hold(&bad));
+
+        // The funct fields 1 and 5 are not shifts.
+        for funct in [1, 5] {
+            let mut bad = lv;
+            for (k, i) in SHIFT_ZERO_BITS.enumerate() {
+                bad[i] = F::from_canonical_u32((funct >> k) & 1);
+            }
+            bad[SHIFT_ZERO_FUNCT] = F::from_canonical_u32(funct);
+            assert!(!constraints_hold(&bad), "funct {funct}");
+        }
+
+        // A funct that does not match its bits.
+        let mut bad = lv;
+        bad[SHIFT_ZERO_FUNCT] = F::from_canonical_u32(funct(BinaryOperator::SRA));
+        assert!(!constraints_hold(&bad));
+    }
+}
@@ -1,3 +1,6 @@
+use core::ops::Range;
+
+use crate::arithmetic::addcy::{eval_ext_circuit_boolean, eval_packed_generic_boolean};
 use crate::arithmetic::columns::*;
 use crate::arithmetic::utils::u32_to_array;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
@@ -10,6 +13,13 @@ use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 
+/// Sign bits of the left and right inputs of SLT and SLTI.
+const SLT_SIGNS: Range<usize> = AUX_INPUT_REGISTER_2.start..AUX_INPUT_REGISTER_2.start + 2;
+/// 2 * (hi - sign * 2^15) for the high limbs of the left and right inputs.
+const SLT_SIGN_RESTS: Range<usize> = SLT_SIGNS.end..SLT_SIGNS.end + 2;
+
+const _: () = check_columns(SLT_SIGNS.start..SLT_SIGN_RESTS.end, SHARED_COLS);
+
 /// Generate row for SLT operations.
 pub(crate) fn generate<F: PrimeField64>(
     lv: &mut [F],
@@ -33,6 +43,10 @@ pub(crate) fn generate<F: PrimeField64>(
             u32_to_array(&mut lv[AUX_INPUT_REGISTER_0], diff);
             u32_to_array(&mut lv[AUX_INPUT_REGISTER_1], cy_val);
             u32_to_array(&mut lv[OUTPUT_REGISTER], rd);
+            for (i, v) in [left_in, right_in].into_iter().enumerate() {
+                lv[SLT_SIGNS.start + i] = F::from_canonical_u32(v >> 31);
+                lv[SLT_SIGN_RESTS.start + i] = F::from_canonical_u32(((v >> 16) & 0x7fff) << 1);
+            }
         }
         IS_SLTU | IS_SLTIU => {
             let (diff, cy) = left_in.overflowing_sub(right_in);
@@ -63,6 +77,32 @@ pub fn eval_packed_generic<P: PackedField>(
     let rd = &lv[AUX_INPUT_REGISTER_1];
 
     eval_packed_generic_slt(yield_constr, is_lt, is_sign, in1, aux, in0, rd, out);
+    eval_packed_generic_signs_differ(lv, yield_constr, is_sign, in0, in1, rd[1]);
+}
+
+/// Constrains `signs_differ` to be sign(x) xor sign(y), assuming
+/// filter != 0.
+fn eval_packed_generic_signs_differ<P: PackedField>(
+    lv: &[P; NUM_ARITH_COLUMNS],
+    yield_constr: &mut ConstraintConsumer<P>,
+    filter: P,
+    x: &[P],
+    y: &[P],
+    signs_differ: P,
+) {
+    let two = P::Scalar::TWO;
+    let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+
+    let signs = &lv[SLT_SIGNS];
+    let his = [x[N_LIMBS - 1], y[N_LIMBS - 1]];
+    for ((&s, &rest), hi) in signs.iter().zip_eq(&lv[SLT_SIGN_RESTS]).zip_eq(his) {
+        yield_constr.constraint(filter * s * (s - P::ONES));
+        // rest is range checked, so s must be the top bit of hi
+        yield_constr.constraint(filter * (rest - hi * two + s * base));
+    }
+
+    let xor = signs[0] + signs[1] - signs[0] * signs[1] * two;
+    yield_constr.constraint(filter * (signs_differ - xor));
 }
 
 pub(crate) fn eval_packed_generic_slt<P: PackedField>(
@@ -99,9 +139,15 @@ pub(crate) fn eval_packed_generic_slt<P: PackedField>(
     }
 
     {
-        yield_constr.constraint(filter * given_cy[0] * (given_cy[0] - P::ONES));
-        yield_constr.constraint(filter * (cy - given_cy[0]) * (P::ONES - sign));
+        eval_packed_generic_boolean(yield_constr, filter, given_cy[0]);
+        // given_cy[1] is zero for unsigned comparisons and sign(left) xor
+        // sign(right) for signed ones. When the signs differ the borrow is
+        // the complement of the signed result.
+        yield_constr.constraint(filter * (cy - given_cy[0]) * (P::ONES - given_cy[1]));
         yield_constr.constraint(filter * given_cy[1] * (P::ONES - cy - given_cy[0]));
+        // The result is a bit on every row, including the last one where
+        // the transition constraint tying it to given_cy[0] is off.
+        eval_packed_generic_boolean(yield_constr, filter, rd[0]);
         yield_constr.constraint_transition(filter * (rd[0] - given_cy[0]));
         for i in 1..N_LIMBS {
             yield_constr.constraint(filter * given_cy[i] * (P::ONES - sign));
@@ -137,6 +183,40 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
         rd,
         out,
     );
+    eval_ext_circuit_signs_differ(builder, lv, yield_constr, is_sign, in0, in1, rd[1]);
+}
+
+fn eval_ext_circuit_signs_differ<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    filter: ExtensionTarget<D>,
+    x: &[ExtensionTarget<D>],
+    y: &[ExtensionTarget<D>],
+    signs_differ: ExtensionTarget<D>,
+) {
+    let base = F::from_canonical_u64(1 << LIMB_BITS);
+
+    let signs = &lv[SLT_SIGNS];
+    let his = [x[N_LIMBS - 1], y[N_LIMBS - 1]];
+    for ((&s, &rest), hi) in signs.iter().zip_eq(&lv[SLT_SIGN_RESTS]).zip_eq(his) {
+        let t0 = builder.mul_sub_extension(s, s, s);
+        let t = builder.mul_extension(filter, t0);
+        yield_constr.constraint(builder, t);
+
+        let t0 = builder.mul_const_extension(F::TWO, hi);
+        let t1 = builder.sub_extension(rest, t0);
+        let t2 = builder.mul_const_add_extension(base, s, t1);
+        let t = builder.mul_extension(filter, t2);
+        yield_constr.constraint(builder, t);
+    }
+
+    // a xor b = a + b - 2ab
+    let t0 = builder.add_extension(signs[0], signs[1]);
+    let xor = builder.arithmetic_extension(-F::TWO, F::ONE, signs[0], signs[1], t0);
+    let t1 = builder.sub_extension(signs_differ, xor);
+    let t = builder.mul_extension(filter, t1);
+    yield_constr.constraint(builder, t);
 }
 
 #[allow(clippy::needless_collect)]
@@ -183,7 +263,8 @@ pub(crate) fn eval_ext_circuit_slt<F: RichField + Extendable<D>, const D: usize>
     }
 
     let good_cy1 = builder.sub_extension(cy, given_cy[0]);
-    let cy_filter1 = builder.mul_extension(good_cy1, not_sign);
+    let same_signs = builder.sub_extension(one, given_cy[1]);
+    let cy_filter1 = builder.mul_extension(good_cy1, same_signs);
     let cy_filter1 = builder.mul_extension(filter, cy_filter1);
 
     let good_cy2 = builder.sub_extension(one, cy);
@@ -191,14 +272,11 @@ pub(crate) fn eval_ext_circuit_slt<F: RichField + Extendable<D>, const D: usize>
     let cy_filter2 = builder.mul_extension(given_cy[1], good_cy2);
     let cy_filter2 = builder.mul_extension(filter, cy_filter2);
 
-    // Check given carry is one bit
-    let bit_constr = builder.mul_sub_extension(given_cy[0], given_cy[0], given_cy[0]);
-    let bit_filter = builder.mul_extension(filter, bit_constr);
-
     {
-        yield_constr.constraint(builder, bit_filter);
+        eval_ext_circuit_boolean(builder, yield_constr, filter, given_cy[0]);
         yield_constr.constraint(builder, cy_filter1);
         yield_constr.constraint(builder, cy_filter2);
+        eval_ext_circuit_boolean(builder, yield_constr, filter, rd[0]);
         let rd_filter = builder.sub_extension(rd[0], given_cy[0]);
         let rd_filter = builder.mul_extension(filter, rd_filter);
         yield_constr.constraint_transition(builder, rd_filter);
@@ -211,3 +289,161 @@ pub(crate) fn eval_ext_circuit_slt<F: RichField + Extendable<D>, const D: usize>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::{Field, Sample};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::arithmetic::utils::sign_extend_imm16;
+    use crate::arithmetic::BinaryOperator;
+
+    type F = GoldilocksField;
+
+    const OPS: [usize; 4] = [IS_SLT, IS_SLTI, IS_SLTU, IS_SLTIU];
+
+    fn operator(filter: usize) -> BinaryOperator {
+        match filter {
+            IS_SLT => BinaryOperator::SLT,
+            IS_SLTI => BinaryOperator::SLTI,
+            IS_SLTU => BinaryOperator::SLTU,
+            IS_SLTIU => BinaryOperator::SLTIU,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Build the row of `filter(left_in, right_in) = rd` on top of random
+    /// 16-bit values and check whether it satisfies the constraints.
+    fn constraints_hold(
+        rng: &mut ChaCha8Rng,
+        filter: usize,
+        left_in: u32,
+        right_in: u32,
+        rd: u32,
+    ) -> bool {
+        let mut lv =
+            [F::default(); NUM_ARITH_COLUMNS].map(|_| F::from_canonical_u16(rng.gen::<u16>()));
+        OPS.map(|i| lv[i] = F::ZERO);
+        lv[filter] = F::ONE;
+
+        generate(&mut lv, filter, left_in, right_in, rd);
+
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(&lv, &mut constraint_consumer);
+        constraint_consumer
+            .constraint_accs
+            .iter()
+            .all(|&acc| acc == F::ZERO)
+    }
+
+    #[test]
+    fn generate_eval_consistency_not_slt() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        let mut lv = [F::default(); NUM_ARITH_COLUMNS].map(|_| F::sample(&mut rng));
+
+        // if the operation filters are all zero, then the constraints
+        // should be met even if all values are garbage.
+        OPS.map(|i| lv[i] = F::ZERO);
+
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(&lv, &mut constraint_consumer);
+        for &acc in &constraint_consumer.constraint_accs {
+            assert_eq!(acc, F::ZERO);
+        }
+    }
+
+    #[test]
+    fn generate_eval_consistency_slt() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        const N_ITERS: usize = 1000;
+
+        for _ in 0..N_ITERS {
+            for filter in OPS {
+                let left_in = rng.gen::<u32>();
+                let right_in = match filter {
+                    IS_SLTI | IS_SLTIU => sign_extend_imm16(rng.gen::<u16>()),
+                    _ => rng.gen::<u32>(),
+                };
+                let (rd, _) = operator(filter).result(left_in, right_in);
+                assert!(constraints_hold(&mut rng, filter, left_in, right_in, rd));
+            }
+        }
+    }
+
+    #[test]
+    fn signed_and_unsigned_comparison() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+        // -1 < 1 signed, but 0xFFFFFFFF > 1 unsigned.
+        assert_eq!(BinaryOperator::SLT.result(0xFFFFFFFF, 1), (1, 0));
+        assert_eq!(BinaryOperator::SLTU.result(0xFFFFFFFF, 1), (0, 0));
+        assert_eq!(BinaryOperator::SLT.result(1, 0xFFFFFFFF), (0, 0));
+        assert_eq!(BinaryOperator::SLTU.result(1, 0xFFFFFFFF), (1, 0));
+        assert_eq!(BinaryOperator::SLT.result(0x80000000, 0x7FFFFFFF), (1, 0));
+
+        // The constraints accept the correct answer and reject the other.
+        for (filter, expected) in [(IS_SLT, 1), (IS_SLTU, 0)] {
+            assert!(constraints_hold(&mut rng, filter, 0xFFFFFFFF, 1, expected));
+            assert!(!constraints_hold(
+                &mut rng,
+                filter,
+                0xFFFFFFFF,
+                1,
+                1 - expected
+            ));
+        }
+    }
+
+    #[test]
+    fn non_boolean_result_is_rejected() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+        for filter in OPS {
+            assert!(!constraints_hold(&mut rng, filter, 1, 2, 2));
+
+            // On the last row only the booleanity of the result catches it.
+            let mut lv =
+                [F::default(); NUM_ARITH_COLUMNS].map(|_| F::from_canonical_u16(rng.gen::<u16>()));
+            OPS.map(|i| lv[i] = F::ZERO);
+            lv[filter] = F::ONE;
+            generate(&mut lv, filter, 1, 2, 2);
+
+            let mut constraint_consumer = ConstraintConsumer::new(
+                vec![GoldilocksField(2), GoldilocksField(3), GoldilocksField(5)],
+                F::ZERO,
+                F::ZERO,
+                F::ONE,
+            );
+            eval_packed_generic(&lv, &mut constraint_consumer);
+            assert!(constraint_consumer
+                .constraint_accs
+                .iter()
+                .any(|&acc| acc != F::ZERO));
+        }
+    }
+
+    #[test]
+    fn forged_signs_are_rejected() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+        // Claim SLT(-1, 1) == 0 by pretending the signs agree, so that
+        // the result is the (zero) unsigned borrow.
+        let mut lv =
+            [F::default(); NUM_ARITH_COLUMNS].map(|_| F::from_canonical_u16(rng.gen::<u16>()));
+        OPS.map(|i| lv[i] = F::ZERO);
+        lv[IS_SLT] = F::ONE;
+        generate(&mut lv, IS_SLT, 0xFFFFFFFF, 1, 0);
+        u32_to_array(&mut lv[AUX_INPUT_REGISTER_1], 0);
+
+        let mut constraint_consumer = test_constraint_consumer();
+        eval_packed_generic(&lv, &mut constraint_consumer);
+        assert!(constraint_consumer
+            .constraint_accs
+            .iter()
+            .any(|&acc| acc != F::ZERO));
+    }
+}
@@ -4,6 +4,12 @@
 //! 32-bit inputs S and A, and produces a 32-bit output C satisfying
 //!
 //!    C = A >> S (mod 2^32)
+//!
+//! Only the low 5 bits of S are used. The row keeps S as the CPU sends
+//! it and shows S = (S mod 32) + 32 k, with S mod 32 and k range checked;
+//! S mod 32 < 32 because 2^11 (S mod 32) is range checked as well.
+
+use core::ops::Range;
 
 use itertools::Itertools;
 use plonky2::field::extension::Extendable;
@@ -19,12 +25,24 @@ use crate::arithmetic::columns::*;
 use crate::arithmetic::div::{
     eval_ext_circuit_divmod_helper, eval_packed_div_helper, generate_divu_helper,
 };
-use crate::arithmetic::utils::{read_value, u32_to_array};
+use crate::arithmetic::mask_shift_amount;
+use crate::arithmetic::utils::u32_to_array;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 
+/// S mod 32, the shift amount the rest of the row uses.
+const SRA_SHIFT_LOW: usize = AUX_INPUT_REGISTER_1.start;
+/// 2^11 (S mod 32).
+const SRA_SHIFT_LOW_SCALED: usize = SRA_SHIFT_LOW + 1;
+/// The quotient k of S = (S mod 32) + 32 k.
+const SRA_SHIFT_QUOTIENT: Range<usize> =
+    AUX_INPUT_REGISTER_2.end + 2..AUX_INPUT_REGISTER_2.end + 2 + N_LIMBS;
+
+const _: () = check_columns(SRA_SHIFT_QUOTIENT, SHARED_COLS);
+
 /// Generates a shift operation (SRA(V).
-/// The inputs are stored in the form `(shift, input, 1 >> shift)`.
-/// NB: if `shift >= 32`, then the third register holds 0.
+/// The inputs are stored in the form `(shift, input, 1 << (shift mod 32))`.
+/// `shift` is the amount as the CPU sends it, e.g. the whole register
+/// of SRAV; only its low 5 bits are used.
 /// We leverage the functions in div.rs to carry out
 /// the computation.
 pub fn generate<F: PrimeField64>(
@@ -43,7 +61,14 @@ pub fn generate<F: PrimeField64>(
     // The second register holds the input which needs shifting.
     u32_to_array(&mut lv[INPUT_REGISTER_1], input);
     u32_to_array(&mut lv[OUTPUT_REGISTER], result);
-    // TODO: If `shift >= 32`, the shifted displacement is set to 0.
+
+    // From here on only `shift mod 32` is used.
+    let raw_shift = shift;
+    let shift = mask_shift_amount(raw_shift);
+    lv[SRA_SHIFT_LOW] = F::from_canonical_u32(shift);
+    lv[SRA_SHIFT_LOW_SCALED] = F::from_canonical_u32(shift << 11);
+    u32_to_array(&mut lv[SRA_SHIFT_QUOTIENT], raw_shift >> 5);
+
     // Compute 1 << shift and store it in the third input register.
     let shifted_displacement = 1u32 << shift;
 
@@ -58,19 +83,20 @@ pub fn generate<F: PrimeField64>(
     lv[AUX_INPUT_REGISTER_2.end + 1] = F::from_canonical_u32(input >> 31);
 
     // set aux data in lv[SRA_EXTRA] and nv[SRA_EXTRA]
-    // We do not check if shift < 32.
     let aux_data = eval_aux_sign_extend(F::from_canonical_u32(shift));
     lv[AUX_EXTRA].copy_from_slice(&aux_data[..8]);
     nv[AUX_EXTRA].copy_from_slice(&aux_data[8..]);
 
-    // This equals to nv[SRA_EXTRA.end-1]
+    // This equals to nv[SRA_EXTRA.end-1], i.e. the top `shift` bits set.
     u32_to_array(
         &mut nv[AUX_INPUT_REGISTER_2],
-        ((1 << shift) - 1) << ((32 - shift) % 32),
+        u32::MAX
+            .checked_shl(32u32.saturating_sub(shift))
+            .unwrap_or(0),
     );
 
     // shift * shift
-    nv[AUX_INPUT_REGISTER_2.end] = F::from_canonical_u32(shift * shift);
+    nv[AUX_INPUT_REGISTER_2.end] = F::from_canonical_u64(shift as u64 * shift as u64);
 
     match filter {
         IS_SRA | IS_SRAV => {
@@ -96,17 +122,24 @@ pub fn eval_packed_generic<P: PackedField>(
     yield_constr: &mut ConstraintConsumer<P>,
 ) {
     let filter = lv[IS_SRA] + lv[IS_SRAV];
-    let shift = &lv[INPUT_REGISTER_0];
-    // The high limbs should be 0.
-    for i in shift.iter().skip(1) {
-        yield_constr.constraint_transition(filter * *i);
-    }
+    let over_flow = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+
+    // raw_shift == shift + 32 * quotient, where shift < 32 since
+    // 2^11 * shift is range checked too.
+    let shift = lv[SRA_SHIFT_LOW];
+    let scaled = P::Scalar::from_canonical_u64(1 << 11);
+    yield_constr.constraint_transition(filter * (lv[SRA_SHIFT_LOW_SCALED] - shift * scaled));
+    let raw_shift = lv[INPUT_REGISTER_0.start] + lv[INPUT_REGISTER_0.start + 1] * over_flow;
+    let quotient = lv[SRA_SHIFT_QUOTIENT.start] + lv[SRA_SHIFT_QUOTIENT.start + 1] * over_flow;
+    yield_constr.constraint_transition(
+        filter * (raw_shift - shift - quotient * P::Scalar::from_canonical_u64(32)),
+    );
+
     // check is_neg is bool
     let is_neg = lv[AUX_INPUT_REGISTER_2.end + 1];
     yield_constr.constraint_transition(filter * is_neg * (P::ONES - is_neg));
 
     // check input is negative or not. We just check the most significant bit in significant limb
-    let over_flow = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
     let add = P::Scalar::from_canonical_u64(1 << (LIMB_BITS - 1));
     let sum = lv[AUX_INPUT_REGISTER_2.end];
     let input_hi = lv[INPUT_REGISTER_1.end - 1];
@@ -114,7 +147,7 @@ pub fn eval_packed_generic<P: PackedField>(
 
     // shift_sq == shift * shift
     let shift_sq = nv[AUX_INPUT_REGISTER_2.end];
-    yield_constr.constraint_transition(filter * (shift_sq - shift[0] * shift[0]));
+    yield_constr.constraint_transition(filter * (shift_sq - shift * shift));
     // Compute the added number if negative
     let intermediate1 = lv[AUX_EXTRA].to_vec();
     let intermediate2 = nv[AUX_EXTRA].to_vec();
@@ -127,7 +160,7 @@ pub fn eval_packed_generic<P: PackedField>(
         .chain(intermediate2.into_iter())
         .zip(coeffs.chunks(2))
     {
-        yield_constr.constraint_transition(filter * (acc * shift_sq + j[0] * shift[0] + j[1] - w));
+        yield_constr.constraint_transition(filter * (acc * shift_sq + j[0] * shift + j[1] - w));
         acc = w;
     }
 
@@ -167,12 +200,33 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) {
     let filter = builder.add_extension(lv[IS_SRA], lv[IS_SRAV]);
-    let shift: [ExtensionTarget<D>; N_LIMBS] = read_value(lv, INPUT_REGISTER_0);
-    // The high limbs should be 0.
-    for i in shift.iter().skip(1) {
-        let t = builder.mul_extension(filter, *i);
+    let over_flow = builder.constant_extension(F::Extension::from_canonical_u64(1 << LIMB_BITS));
+
+    // raw_shift == shift + 32 * quotient
+    let shift = lv[SRA_SHIFT_LOW];
+    {
+        let t0 = builder.mul_const_extension(F::from_canonical_u64(1 << 11), shift);
+        let t1 = builder.sub_extension(lv[SRA_SHIFT_LOW_SCALED], t0);
+        let t = builder.mul_extension(filter, t1);
+        yield_constr.constraint_transition(builder, t);
+
+        let raw_shift = builder.mul_add_extension(
+            lv[INPUT_REGISTER_0.start + 1],
+            over_flow,
+            lv[INPUT_REGISTER_0.start],
+        );
+        let quotient = builder.mul_add_extension(
+            lv[SRA_SHIFT_QUOTIENT.start + 1],
+            over_flow,
+            lv[SRA_SHIFT_QUOTIENT.start],
+        );
+        let t0 = builder.sub_extension(raw_shift, shift);
+        let t1 = builder.mul_const_extension(F::from_canonical_u64(32), quotient);
+        let t2 = builder.sub_extension(t0, t1);
+        let t = builder.mul_extension(filter, t2);
         yield_constr.constraint_transition(builder, t);
     }
+
     // check is_neg is bool
     let is_neg = lv[AUX_INPUT_REGISTER_2.end + 1];
     {
@@ -183,7 +237,6 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     }
 
     // check input is negative or not. We just check the most significant bit in significant limb
-    let over_flow = builder.constant_extension(F::Extension::from_canonical_u64(1 << LIMB_BITS));
     {
         let add =
             builder.constant_extension(F::Extension::from_canonical_u64(1 << (LIMB_BITS - 1)));
@@ -199,7 +252,7 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
 
     // shift_sq == shift * shift
     let shift_sq = nv[AUX_INPUT_REGISTER_2.end];
-    let sq = builder.square_extension(shift[0]);
+    let sq = builder.square_extension(shift);
     let t0 = builder.sub_extension(shift_sq, sq);
     let t = builder.mul_extension(filter, t0);
     yield_constr.constraint_transition(builder, t);
@@ -222,7 +275,7 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
             .chain(intermediate2.into_iter())
             .zip(coeffs.chunks(2))
         {
-            let t0 = builder.wide_arithmetic_extension(acc, shift_sq, j[0], shift[0], j[1]);
+            let t0 = builder.wide_arithmetic_extension(acc, shift_sq, j[0], shift, j[1]);
             let t = builder.sub_extension(t0, w);
             let constr = builder.mul_extension(filter, t);
             yield_constr.constraint_transition(builder, constr);
@@ -306,8 +359,13 @@ fn eval_aux_sign_extend<F: Field>(x: F) -> Vec<F> {
 
 #[cfg(test)]
 mod tests {
-    use crate::arithmetic::columns::{IS_SRA, IS_SRAV, NUM_ARITH_COLUMNS};
-    use crate::arithmetic::sra::{eval_packed_generic, eval_poly, generate, sign_extend_poly};
+    use crate::arithmetic::columns::{IS_SRA, IS_SRAV, NUM_ARITH_COLUMNS, OUTPUT_REGISTER};
+    use crate::arithmetic::sra::{
+        eval_packed_generic, eval_poly, generate, sign_extend_poly, SRA_SHIFT_QUOTIENT,
+    };
+    use crate::arithmetic::tests::test_constraint_consumer;
+    use crate::arithmetic::utils::u32_to_array;
+    use crate::arithmetic::BinaryOperator;
     use crate::constraint_consumer::ConstraintConsumer;
     use plonky2::field::goldilocks_field::GoldilocksField;
     use plonky2::field::types::{Field, Sample};
@@ -393,8 +451,13 @@ mod tests {
                 lv[op_filter] = F::ONE;
 
                 let input0: u32 = rng.gen();
-                let input1: u32 = rng.gen_range(0..32);
-                let result = ((input0 as i32) >> input1) as u32;
+                // Amounts of 32 or more shift by their low 5 bits.
+                let input1: u32 = if rng.gen() {
+                    rng.gen_range(0..32)
+                } else {
+                    rng.gen()
+                };
+                let result = ((input0 as i32) >> (input1 % 32)) as u32;
 
                 generate(&mut lv, &mut nv, op_filter, input1, input0, result);
 
@@ -411,4 +474,64 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn sign_extends_negative_input() {
+        type F = GoldilocksField;
+
+        // (input, shift, result)
+        let cases = [
+            (0x80000000u32, 4u32, 0xF8000000u32),
+            (0x80000000, 31, 0xFFFFFFFF),
+            (0x80000000, 0, 0x80000000),
+            (0x7FFFFFFF, 4, 0x07FFFFFF),
+            (0xFFFFFFF0, 2, 0xFFFFFFFC),
+            (0x80000000, 36, 0xF8000000),
+            (0x80000000, 0xFFFFFFFF, 0xFFFFFFFF),
+        ];
+
+        for op_filter in SRA_OPS {
+            for (input, shift, result) in cases {
+                assert_eq!(BinaryOperator::SRA.result(input, shift), (result, 0));
+                assert_eq!(BinaryOperator::SRAV.result(input, shift), (result, 0));
+
+                let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+                let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+                lv[op_filter] = F::ONE;
+
+                generate(&mut lv, &mut nv, op_filter, shift, input, result);
+
+                let mut expected = [F::ZERO; 2];
+                u32_to_array(&mut expected, result);
+                assert_eq!(&lv[OUTPUT_REGISTER], &expected[..]);
+
+                let mut constraint_consumer = test_constraint_consumer();
+                eval_packed_generic(&lv, &nv, &mut constraint_consumer);
+                for &acc in &constraint_consumer.constraint_accs {
+                    assert_eq!(acc, GoldilocksField::ZERO);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quotient_must_match_amount() {
+        type F = GoldilocksField;
+
+        for op_filter in SRA_OPS {
+            let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+            let mut nv = [F::ZERO; NUM_ARITH_COLUMNS];
+            lv[op_filter] = F::ONE;
+            generate(&mut lv, &mut nv, op_filter, 40, 0x80000000, 0xFF800000);
+            // Claim 40 = 8 + 32 * 2.
+            lv[SRA_SHIFT_QUOTIENT.start] += F::ONE;
+
+            let mut constraint_consumer = test_constraint_consumer();
+            eval_packed_generic(&lv, &nv, &mut constraint_consumer);
+            assert!(constraint_consumer
+                .constraint_accs
+                .iter()
+                .any(|&acc| acc != GoldilocksField::ZERO));
+        }
+    }
 }
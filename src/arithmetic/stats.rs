@@ -0,0 +1,39 @@
+//! Per-operator counts of the operations in an arithmetic trace, for
+//! seeing which operators dominate real workloads and so are worth
+//! optimising.
+//!
+//! The trace builder records every operation it turns into rows, so the
+//! counts are those of the trace that is proven; a shift by zero counts
+//! as its shift, although it takes an `IS_SHIFT_ZERO` row.
+
+use std::collections::HashMap;
+
+use super::api::ArithOperator;
+use super::Operation;
+
+/// The number of operations of each operator in a trace.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ArithmeticStats {
+    counts: HashMap<ArithOperator, usize>,
+}
+
+impl ArithmeticStats {
+    pub(crate) fn record(&mut self, operation: &Operation) {
+        *self.counts.entry(ArithOperator::of(operation)).or_default() += 1;
+    }
+
+    /// The number of operations of `operator`.
+    pub fn count(&self, operator: ArithOperator) -> usize {
+        self.counts.get(&operator).copied().unwrap_or(0)
+    }
+
+    /// The number of operations of each operator that occurs.
+    pub fn counts(&self) -> &HashMap<ArithOperator, usize> {
+        &self.counts
+    }
+
+    /// The number of operations.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
@@ -0,0 +1,632 @@
+//! Differential tests of the arithmetic table against a reference ALU.
+//!
+//! For every operator we build random operations, check `result`
+//! against an independent implementation of the MIPS semantics (after
+//! `mips_emulator::state::InstrumentedState::execute` and
+//! `handle_hilo`), and check that the rows produced by `to_rows`
+//! satisfy all constraints of the arithmetic STARK.
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, Field64};
+use proptest::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::arithmetic::arithmetic_stark::ArithmeticStark;
+use crate::arithmetic::columns::{
+    INPUT_REGISTER_0, IS_ADD, NUM_ARITH_COLUMNS, NUM_SHARED_COLS, OPERATOR_ID, START_SHARED_COLS,
+};
+use crate::arithmetic::utils::sign_extend_imm16;
+use crate::arithmetic::{
+    debug_assert_limbs_in_range, debug_assert_row_order, estimated_rows, trace_cost,
+    BinaryOperator, Operation, RowCost,
+};
+use crate::constraint_consumer::ConstraintConsumer;
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::stark::Stark;
+
+type F = GoldilocksField;
+const D: usize = 2;
+
+const N_RND_TESTS: usize = 10_000;
+
+const BINARY_OPERATORS: [BinaryOperator; 27] = [
+    BinaryOperator::ADD,
+    BinaryOperator::ADDU,
+    BinaryOperator::ADDI,
+    BinaryOperator::ADDIU,
+    BinaryOperator::SUB,
+    BinaryOperator::SUBU,
+    BinaryOperator::MULT,
+    BinaryOperator::MULTU,
+    BinaryOperator::MUL,
+    BinaryOperator::DIV,
+    BinaryOperator::DIVU,
+    BinaryOperator::SLLV,
+    BinaryOperator::SRLV,
+    BinaryOperator::SRAV,
+    BinaryOperator::SLL,
+    BinaryOperator::SRL,
+    BinaryOperator::SRA,
+    BinaryOperator::SLT,
+    BinaryOperator::SLTU,
+    BinaryOperator::SLTI,
+    BinaryOperator::SLTIU,
+    BinaryOperator::LUI,
+    BinaryOperator::MFHI,
+    BinaryOperator::MTHI,
+    BinaryOperator::MFLO,
+    BinaryOperator::MTLO,
+    BinaryOperator::NOOP,
+];
+
+/// Random word, biased towards values near 0 and the sign boundary so
+/// that carries and sign changes are hit often.
+fn random_word(rng: &mut ChaCha8Rng) -> u32 {
+    match rng.gen_range(0..8) {
+        0 => rng.gen_range(0..4),
+        1 => u32::MAX - rng.gen_range(0..4),
+        2 => 0x8000_0000 ^ rng.gen_range(0..4),
+        3 => 0x7FFF_FFFF - rng.gen_range(0..4),
+        4 => rng.gen::<u32>() >> rng.gen_range(0..32),
+        _ => rng.gen(),
+    }
+}
+
+/// Inputs in the form the witness generator passes them: immediates are
+/// already sign-extended, immediate shift amounts are below 32 and LUI
+/// takes the raw 16-bit immediate.
+fn random_inputs(rng: &mut ChaCha8Rng, operator: BinaryOperator) -> (u32, u32) {
+    let input0 = random_word(rng);
+    let input1 = random_word(rng);
+    match operator {
+        BinaryOperator::ADDI
+        | BinaryOperator::ADDIU
+        | BinaryOperator::SLTI
+        | BinaryOperator::SLTIU => (input0, sign_extend_imm16(input1 as u16)),
+        BinaryOperator::SLL | BinaryOperator::SRL | BinaryOperator::SRA => (input0, input1 & 0x1f),
+        BinaryOperator::LUI => (input0 & 0xffff, 0),
+        _ => (input0, input1),
+    }
+}
+
+fn reference_binary(operator: BinaryOperator, rs: u32, rt: u32) -> (u32, u32) {
+    // Shifts only use the low 5 bits of their amount.
+    let rt = if operator.is_simulated() { rt % 32 } else { rt };
+    let lo = |x: u64| x as u32;
+    let hi = |x: u64| (x >> 32) as u32;
+    match operator {
+        BinaryOperator::ADD
+        | BinaryOperator::ADDU
+        | BinaryOperator::ADDI
+        | BinaryOperator::ADDIU => ((rs as u64 + rt as u64) as u32, 0),
+        BinaryOperator::SUB | BinaryOperator::SUBU => ((rs as i64 - rt as i64) as u32, 0),
+        BinaryOperator::MUL => (lo(rs as u64 * rt as u64), 0),
+        BinaryOperator::MULT => {
+            let acc = ((rs as i32) as i64 * (rt as i32) as i64) as u64;
+            (lo(acc), hi(acc))
+        }
+        BinaryOperator::MULTU => {
+            let acc = rs as u64 * rt as u64;
+            (lo(acc), hi(acc))
+        }
+        // The arithmetic table pins division by zero to (0, rs).
+        BinaryOperator::DIV | BinaryOperator::DIVU if rt == 0 => (0, rs),
+        BinaryOperator::DIV => {
+            let (n, d) = ((rs as i32) as i64, (rt as i32) as i64);
+            ((n / d) as u32, (n % d) as u32)
+        }
+        BinaryOperator::DIVU => (rs / rt, rs % rt),
+        BinaryOperator::SLL | BinaryOperator::SLLV => (rs << rt, 0),
+        BinaryOperator::SRL | BinaryOperator::SRLV => (rs >> rt, 0),
+        BinaryOperator::SRA | BinaryOperator::SRAV => {
+            let shifted = rs >> rt;
+            let sign_bits = if rs & 0x8000_0000 != 0 && rt != 0 {
+                !(u32::MAX >> rt)
+            } else {
+                0
+            };
+            (shifted | sign_bits, 0)
+        }
+        BinaryOperator::SLT | BinaryOperator::SLTI => (((rs as i32) < (rt as i32)) as u32, 0),
+        BinaryOperator::SLTU | BinaryOperator::SLTIU => ((rs < rt) as u32, 0),
+        BinaryOperator::LUI => (rs << 16, 0),
+        BinaryOperator::MFHI
+        | BinaryOperator::MTHI
+        | BinaryOperator::MFLO
+        | BinaryOperator::MTLO => (rs, 0),
+        BinaryOperator::NOOP => (0, 0),
+    }
+}
+
+/// The consumer the constraint checks of the module tests evaluate
+/// into. The rows are taken to be in the middle of the trace: the
+/// transition constraints apply, the first and last row ones do not.
+pub(crate) fn test_constraint_consumer() -> ConstraintConsumer<F> {
+    ConstraintConsumer::new(
+        vec![GoldilocksField(2), GoldilocksField(3), GoldilocksField(5)],
+        F::ONE,
+        F::ZERO,
+        F::ZERO,
+    )
+}
+
+/// Evaluate every constraint of the arithmetic STARK on the rows of
+/// `op`, followed by a zero row for one-row operations. The range
+/// check boundary constraints are disabled since the rows are not
+/// part of a full trace.
+fn constraints_hold(op: &Operation) -> bool {
+    let (lv, nv) = op.to_rows::<F>();
+    let nv = nv.unwrap_or_else(|| vec![F::ZERO; NUM_ARITH_COLUMNS]);
+    rows_satisfy_constraints(&lv, &nv)
+}
+
+/// Like `constraints_hold`, on the rows `lv` and `nv` themselves.
+fn rows_satisfy_constraints(lv: &[F], nv: &[F]) -> bool {
+    let stark = ArithmeticStark::<F, D>::default();
+    let frame = StarkFrame::<F, NUM_ARITH_COLUMNS>::from_values(lv, nv);
+    let mut consumer = test_constraint_consumer();
+    stark.eval_packed_base(&frame, &mut consumer);
+    consumer.constraint_accs.iter().all(|&acc| acc == F::ZERO)
+}
+
+#[test]
+fn binary_operators_match_reference() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+    for operator in BINARY_OPERATORS {
+        for _ in 0..N_RND_TESTS {
+            let (input0, input1) = random_inputs(&mut rng, operator);
+            let op = Operation::binary(operator, input0, input1);
+            assert_eq!(
+                op.result(),
+                reference_binary(operator, input0, input1),
+                "{operator:?}({input0:#x}, {input1:#x})"
+            );
+            assert!(
+                constraints_hold(&op),
+                "constraints failed for {operator:?}({input0:#x}, {input1:#x})"
+            );
+        }
+    }
+}
+
+#[test]
+fn variable_shifts_mask_amount() {
+    let x = 0x8765_4321;
+    for operator in [
+        BinaryOperator::SLLV,
+        BinaryOperator::SRLV,
+        BinaryOperator::SRAV,
+    ] {
+        for (shift, masked) in [(32, 0), (33, 1), (63, 31), (0xFFFF_FFE4, 4)] {
+            let op = Operation::binary(operator, x, shift);
+            assert_eq!(op.result(), operator.result(x, masked), "{operator:?}");
+            assert!(constraints_hold(&op), "{operator:?}({x:#x}, {shift})");
+        }
+    }
+    assert_eq!(BinaryOperator::SLLV.result(x, 32), (x, 0));
+    assert_eq!(BinaryOperator::SLLV.result(x, 33), (x << 1, 0));
+}
+
+#[test]
+fn fixed_shifts_mask_amount() {
+    // MIPS shifts by the low 5 bits of the amount, as Rust's wrapping
+    // shifts do.
+    let mips = |operator, x: u32, s: u32| match operator {
+        BinaryOperator::SLL => x.wrapping_shl(s),
+        BinaryOperator::SRL => x.wrapping_shr(s),
+        _ => (x as i32).wrapping_shr(s) as u32,
+    };
+
+    let x = 0x8765_4321;
+    for operator in [
+        BinaryOperator::SLL,
+        BinaryOperator::SRL,
+        BinaryOperator::SRA,
+    ] {
+        for shift in [32, 34, 63, 0xFFFF_FFE4] {
+            let op = Operation::binary(operator, x, shift);
+            assert_eq!(op.result(), (mips(operator, x, shift), 0), "{operator:?}");
+            assert_eq!(op.result(), operator.result(x, shift % 32), "{operator:?}");
+            assert!(constraints_hold(&op), "{operator:?}({x:#x}, {shift})");
+        }
+    }
+    assert_eq!(BinaryOperator::SLL.result(x, 34), (x << 2, 0));
+}
+
+#[test]
+fn shift_rows_keep_the_unmasked_amount() {
+    let x = 0x8765_4321;
+    for operator in [
+        BinaryOperator::SLLV,
+        BinaryOperator::SRLV,
+        BinaryOperator::SRAV,
+        BinaryOperator::SLL,
+        BinaryOperator::SRL,
+        BinaryOperator::SRA,
+    ] {
+        for shift in [1, 33, 63, 0xFFFF_FFE4] {
+            let op = Operation::binary(operator, x, shift);
+            let (lv, _) = op.to_rows::<F>();
+            let amount = [shift & 0xffff, shift >> 16].map(F::from_canonical_u32);
+            assert_eq!(
+                lv[INPUT_REGISTER_0], amount,
+                "{operator:?}({x:#x}, {shift})"
+            );
+            assert!(constraints_hold(&op), "{operator:?}({x:#x}, {shift})");
+        }
+    }
+}
+
+#[test]
+fn shift_constructors_match_binary() {
+    let x = 0x8765_4321;
+    for (shift, masked) in [(3, 3), (0, 0), (31, 31), (35, 3), (0xFFFF_FFE4, 4)] {
+        let op = Operation::shift_left(x, shift);
+        assert_eq!(op, Operation::binary(BinaryOperator::SLL, x, masked));
+        assert_eq!(op.result(), (x << masked, 0));
+
+        let op = Operation::shift_right(x, shift);
+        assert_eq!(op, Operation::binary(BinaryOperator::SRL, x, masked));
+        assert_eq!(op.result(), (x >> masked, 0));
+    }
+}
+
+#[test]
+fn estimated_rows_matches_to_rows() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+    let mut ops = vec![];
+    for operator in BINARY_OPERATORS {
+        let (input0, input1) = random_inputs(&mut rng, operator);
+        ops.push(Operation::binary(operator, input0, input1));
+    }
+
+    let rows: usize = ops
+        .iter()
+        .map(|op| match op.to_rows::<F>() {
+            (_, None) => 1,
+            (_, Some(_)) => 2,
+        })
+        .sum();
+    assert_eq!(estimated_rows(&ops), rows);
+    assert_eq!(estimated_rows(&[]), 0);
+}
+
+#[test]
+fn trace_cost_counts_rows_and_range_checks() {
+    let add = trace_cost(&Operation::binary(BinaryOperator::ADD, 1, 2));
+    assert_eq!(add.rows, 1);
+    assert_eq!(add.range_checks, NUM_SHARED_COLS);
+
+    let div = trace_cost(&Operation::binary(BinaryOperator::DIV, 7, 2));
+    assert_eq!(div.rows, 2);
+    assert_eq!(div.range_checks, 2 * NUM_SHARED_COLS);
+
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+    let ops: Vec<_> = BINARY_OPERATORS
+        .into_iter()
+        .map(|operator| {
+            let (input0, input1) = random_inputs(&mut rng, operator);
+            Operation::binary(operator, input0, input1)
+        })
+        .collect();
+    let total: RowCost = ops.iter().map(trace_cost).sum();
+    assert_eq!(total.rows, estimated_rows(&ops));
+    assert_eq!(total.range_checks, total.rows * NUM_SHARED_COLS);
+}
+
+#[test]
+fn to_rows_into_matches_to_rows() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+    let mut ops = vec![];
+    for operator in BINARY_OPERATORS {
+        let (input0, input1) = random_inputs(&mut rng, operator);
+        ops.push(Operation::binary(operator, input0, input1));
+    }
+
+    // Reused buffers still holding the previous operation's rows.
+    let mut row = vec![F::NEG_ONE; NUM_ARITH_COLUMNS];
+    let mut next = vec![F::NEG_ONE; NUM_ARITH_COLUMNS];
+    for op in &ops {
+        let (lv, nv) = op.to_rows::<F>();
+        let has_next = op.to_rows_into(&mut row, &mut next);
+        assert_eq!(row, lv, "{op:?}");
+        assert_eq!(has_next, nv.is_some(), "{op:?}");
+        if let Some(nv) = nv {
+            assert_eq!(next, nv, "{op:?}");
+        }
+    }
+}
+
+#[test]
+fn second_row_has_no_operation_filter() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+    let mut ops = vec![];
+    for operator in BINARY_OPERATORS {
+        let (input0, input1) = random_inputs(&mut rng, operator);
+        ops.push(Operation::binary(operator, input0, input1));
+    }
+
+    for op in &ops {
+        let (lv, nv) = op.to_rows::<F>();
+        let filters = &lv[..START_SHARED_COLS];
+        assert_eq!(filters.iter().filter(|x| !x.is_zero()).count(), 1, "{op:?}");
+        assert_eq!(filters.iter().copied().sum::<F>(), F::ONE, "{op:?}");
+        assert_eq!(nv.is_some(), op.num_rows() == 2, "{op:?}");
+        if let Some(nv) = nv {
+            assert!(
+                nv[..START_SHARED_COLS].iter().all(|x| x.is_zero()),
+                "{op:?}"
+            );
+        }
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "of the first row is 0")]
+fn swapped_rows_are_caught() {
+    let op = Operation::binary(BinaryOperator::DIVU, 7, 2);
+    let (lv, nv) = op.to_rows::<F>();
+    let nv = nv.unwrap();
+    debug_assert_row_order(&op, &nv, Some(&lv));
+}
+
+#[test]
+fn div_second_row_sets_no_stray_columns() {
+    let op = Operation::binary(BinaryOperator::DIV, 0xffff_fff9, 2);
+    let (lv, nv) = op.to_rows::<F>();
+    let nv = nv.unwrap();
+    debug_assert_row_order(&op, &lv, Some(&nv));
+
+    for (col, &x) in nv.iter().enumerate() {
+        if col < START_SHARED_COLS || col == OPERATOR_ID {
+            assert!(x.is_zero(), "column {col} of the second row is {x}");
+        }
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "operation filter 0 is set in the second row")]
+fn stray_filter_in_second_row_is_caught() {
+    let op = Operation::binary(BinaryOperator::DIV, 0xffff_fff9, 2);
+    let (lv, nv) = op.to_rows::<F>();
+    let mut nv = nv.unwrap();
+    nv[IS_ADD] = F::ONE;
+    debug_assert_row_order(&op, &lv, Some(&nv));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "is set in the second row, which only uses")]
+fn operator_id_in_second_row_is_caught() {
+    let op = Operation::binary(BinaryOperator::DIVU, 7, 2);
+    let (lv, nv) = op.to_rows::<F>();
+    let mut nv = nv.unwrap();
+    nv[OPERATOR_ID] = F::from_canonical_u32(BinaryOperator::DIVU.id());
+    debug_assert_row_order(&op, &lv, Some(&nv));
+}
+
+#[test]
+fn all_lists_every_operator() {
+    assert_eq!(BinaryOperator::all(), BINARY_OPERATORS);
+
+    assert!(BinaryOperator::SLL.is_simulated());
+    assert!(BinaryOperator::SRAV.is_simulated());
+    assert!(!BinaryOperator::ADD.is_simulated());
+    assert!(!BinaryOperator::MUL.is_simulated());
+}
+
+#[test]
+fn binary_imm_sign_extends() {
+    assert_eq!(sign_extend_imm16(0x0000), 0);
+    assert_eq!(sign_extend_imm16(0x7FFF), 0x0000_7FFF);
+    assert_eq!(sign_extend_imm16(0x8000), 0xFFFF_8000);
+    assert_eq!(sign_extend_imm16(0xFFFF), 0xFFFF_FFFF);
+
+    // 5 + (-1) == 4
+    for operator in [BinaryOperator::ADDI, BinaryOperator::ADDIU] {
+        let op = Operation::binary_imm(operator, 5, 0xFFFF);
+        assert_eq!(op.result(), (4, 0), "{operator:?}");
+        assert!(constraints_hold(&op), "{operator:?}");
+    }
+    // 5 < -1 is false signed, true unsigned.
+    let op = Operation::binary_imm(BinaryOperator::SLTI, 5, 0xFFFF);
+    assert_eq!(op.result(), (0, 0));
+    let op = Operation::binary_imm(BinaryOperator::SLTIU, 5, 0xFFFF);
+    assert_eq!(op.result(), (1, 0));
+}
+
+#[test]
+fn try_result_reports_fallbacks() {
+    use crate::arithmetic::ArithmeticError;
+
+    let cases = [
+        (BinaryOperator::DIV, 7, 0, ArithmeticError::DivByZero),
+        (BinaryOperator::DIVU, 7, 0, ArithmeticError::DivByZero),
+    ];
+    for (operator, input0, input1, err) in cases {
+        assert_eq!(
+            operator.try_result(input0, input1),
+            Err(err),
+            "{operator:?}"
+        );
+    }
+    // The fallback results themselves are unchanged.
+    assert_eq!(BinaryOperator::DIVU.result(7, 0), (0, 7));
+
+    assert_eq!(BinaryOperator::SLL.try_result(7, 31), Ok((7 << 31, 0)));
+    // Shift amounts are masked, so no shift is out of range.
+    assert_eq!(BinaryOperator::SLL.try_result(7, 32), Ok((7, 0)));
+    assert_eq!(BinaryOperator::SLLV.try_result(7, 32), Ok((7, 0)));
+}
+
+#[test]
+fn checked_policy_reports_overflow() {
+    use crate::arithmetic::OverflowPolicy::{Checked, Wrap};
+
+    assert_eq!(
+        BinaryOperator::ADDU.result_with_policy(u32::MAX, 1, Wrap),
+        Some((0, 0))
+    );
+    assert_eq!(
+        BinaryOperator::ADDU.result_with_policy(u32::MAX, 1, Checked),
+        None
+    );
+    // -1 + 1 does not overflow as a signed addition.
+    assert_eq!(
+        BinaryOperator::ADD.result_with_policy(u32::MAX, 1, Checked),
+        Some((0, 0))
+    );
+    assert_eq!(
+        BinaryOperator::ADD.result_with_policy(0x7fff_ffff, 1, Checked),
+        None
+    );
+    assert_eq!(BinaryOperator::SUBU.result_with_policy(0, 1, Checked), None);
+    assert_eq!(
+        BinaryOperator::MUL.result_with_policy(0x1_0000, 0x1_0000, Checked),
+        None
+    );
+    assert_eq!(
+        BinaryOperator::DIV.result_with_policy(0x8000_0000, u32::MAX, Checked),
+        None
+    );
+    assert_eq!(BinaryOperator::DIVU.result_with_policy(1, 0, Checked), None);
+
+    // Wrap always agrees with result, and Checked agrees wherever it answers.
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+    for operator in BINARY_OPERATORS {
+        for _ in 0..100 {
+            let (input0, input1) = random_inputs(&mut rng, operator);
+            let wrapped = operator.result(input0, input1);
+            assert_eq!(
+                operator.result_with_policy(input0, input1, Wrap),
+                Some(wrapped)
+            );
+            if let Some(checked) = operator.result_with_policy(input0, input1, Checked) {
+                assert_eq!(checked, wrapped, "{operator:?}({input0:#x}, {input1:#x})");
+            }
+        }
+    }
+}
+
+#[test]
+fn mul_writes_the_low_word_of_mult() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+    for _ in 0..N_RND_TESTS {
+        let (input0, input1) = (rng.gen::<u32>(), rng.gen::<u32>());
+        let mul = Operation::binary(BinaryOperator::MUL, input0, input1);
+        let mult = Operation::binary(BinaryOperator::MULT, input0, input1);
+        let (mul_lo, mul_hi) = mul.result();
+        let (mult_lo, _) = mult.result();
+        assert_eq!(mul_lo, mult_lo, "{input0:#x} * {input1:#x}");
+        assert_eq!(mul_hi, 0);
+    }
+
+    assert!(!BinaryOperator::MUL.writes_hi_lo());
+    assert!(BinaryOperator::MULT.writes_hi_lo());
+    assert!(BinaryOperator::MULTU.writes_hi_lo());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "which is not a 16-bit limb")]
+fn out_of_range_limb_is_caught() {
+    let (mut row, _) = Operation::binary(BinaryOperator::ADD, 1, 2).to_rows::<F>();
+    // A 32-bit word packed as a single limb.
+    row[INPUT_REGISTER_0.start] = F::from_canonical_u32(0x1_0002);
+    debug_assert_limbs_in_range(BinaryOperator::ADD, 1, 2, &row, None);
+}
+
+/// A few random operations of every operator, including shifts by zero.
+fn soundness_operations(rng: &mut ChaCha8Rng) -> Vec<Operation> {
+    let mut ops = vec![];
+    for _ in 0..3 {
+        for operator in BINARY_OPERATORS {
+            let (input0, input1) = random_inputs(rng, operator);
+            ops.push(Operation::binary(operator, input0, input1));
+        }
+        ops.push(Operation::binary(
+            BinaryOperator::SRAV,
+            random_word(rng),
+            32,
+        ));
+    }
+    ops
+}
+
+/// Soundness smoke test: adding a random nonzero value to any column an
+/// operation writes, in either of its rows, must break some constraint.
+/// A column that an operation sets but no constraint reads would pass
+/// here while letting a prover choose its value.
+#[test]
+fn corrupted_columns_are_rejected() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+
+    for op in soundness_operations(&mut rng) {
+        let (lv, nv) = op.to_rows::<F>();
+        let nv = nv.unwrap_or_else(|| vec![F::ZERO; NUM_ARITH_COLUMNS]);
+        assert!(rows_satisfy_constraints(&lv, &nv), "{op:?}");
+
+        for (row, values) in [(0, &lv), (1, &nv)] {
+            for col in (0..NUM_ARITH_COLUMNS).filter(|&col| values[col] != F::ZERO) {
+                let delta = F::from_noncanonical_u64(rng.gen_range(1..F::ORDER));
+                let (mut lv, mut nv) = (lv.clone(), nv.clone());
+                let corrupted = if row == 0 { &mut lv } else { &mut nv };
+                corrupted[col] += delta;
+                assert!(
+                    !rows_satisfy_constraints(&lv, &nv),
+                    "{op:?}: column {col} of row {row} is not bound by any constraint"
+                );
+            }
+        }
+    }
+}
+
+/// A word, biased towards the edge cases.
+fn word() -> impl Strategy<Value = u32> {
+    prop_oneof![
+        Just(0),
+        Just(1),
+        Just(u32::MAX),
+        Just(i32::MIN as u32),
+        Just(i32::MAX as u32),
+        any::<u32>(),
+    ]
+}
+
+/// Any operation, with shift amounts in `0..64` for the shifts.
+fn any_operation() -> impl Strategy<Value = Operation> {
+    (
+        prop::sample::select(BinaryOperator::all()),
+        word(),
+        word(),
+        0u32..64,
+    )
+        .prop_map(|(operator, input0, input1, shift)| {
+            let input1 = if operator.is_simulated() {
+                shift
+            } else {
+                input1
+            };
+            Operation::binary(operator, input0, input1)
+        })
+}
+
+proptest! {
+    #[test]
+    fn to_rows_is_total(op in any_operation()) {
+        let (row, next) = op.to_rows::<F>();
+        prop_assert_eq!(row.len(), NUM_ARITH_COLUMNS);
+        prop_assert_eq!(next.is_some(), op.num_rows() == 2);
+        if let Some(next) = next {
+            prop_assert_eq!(next.len(), NUM_ARITH_COLUMNS);
+        }
+    }
+}
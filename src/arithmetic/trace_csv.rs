@@ -0,0 +1,93 @@
+//! Dump of arithmetic trace rows to CSV, for reading a failing trace by
+//! eye while debugging the constraints.
+//!
+//! The first line holds the names of the columns, as given by
+//! `columns::column_names`, and every other line one row of the trace,
+//! with each value as its canonical `u64`. The trace of
+//! `api::generate_trace` is given by columns, so transpose it first
+//! (see `plonky2::util::transpose`).
+
+use std::io::{self, Write};
+
+use plonky2::field::types::PrimeField64;
+
+use super::columns::{column_names, NUM_ARITH_COLUMNS};
+
+/// Write `trace`, given as rows of `NUM_ARITH_COLUMNS` values, to `w`
+/// as CSV with a header line. Fails with `InvalidInput` on a row of
+/// another length, having written the rows before it.
+pub fn trace_to_csv<F: PrimeField64>(trace: &[Vec<F>], mut w: impl Write) -> io::Result<()> {
+    writeln!(w, "{}", column_names().join(","))?;
+
+    for (i, row) in trace.iter().enumerate() {
+        if row.len() != NUM_ARITH_COLUMNS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "row {i} has {} columns instead of {NUM_ARITH_COLUMNS}",
+                    row.len()
+                ),
+            ));
+        }
+        let values = row
+            .iter()
+            .map(|x| x.to_canonical_u64().to_string())
+            .collect::<Vec<_>>();
+        writeln!(w, "{}", values.join(","))?;
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::*;
+    use crate::arithmetic::columns::{IS_DIVU, IS_NOOP, OUTPUT_REGISTER};
+    use crate::arithmetic::{BinaryOperator, Operation};
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn csv_has_a_header_and_one_line_per_row() {
+        let (add, _) = Operation::binary(BinaryOperator::ADDU, 5, 7).to_rows::<F>();
+        let (div, rem) = Operation::binary(BinaryOperator::DIVU, 7, 2).to_rows::<F>();
+        let trace = vec![add, div, rem.unwrap()];
+
+        let mut out = Vec::new();
+        trace_to_csv(&trace, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + trace.len());
+
+        let header = lines[0].split(',').collect::<Vec<_>>();
+        assert_eq!(header.len(), NUM_ARITH_COLUMNS);
+        assert_eq!(header, column_names());
+        assert_eq!(header[IS_NOOP], "IS_NOOP");
+
+        for (line, row) in lines[1..].iter().zip(&trace) {
+            let values = line
+                .split(',')
+                .map(|v| v.parse::<u64>().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(values.len(), NUM_ARITH_COLUMNS);
+            assert!(values
+                .iter()
+                .zip(row)
+                .all(|(&v, x)| v == x.to_canonical_u64()));
+        }
+
+        // 7 / 2 = 3 in the first row of the DIVU.
+        let divu: Vec<&str> = lines[2].split(',').collect();
+        assert_eq!(divu[IS_DIVU], "1");
+        assert_eq!(divu[OUTPUT_REGISTER.start], "3");
+    }
+
+    #[test]
+    fn short_rows_are_rejected() {
+        let trace = vec![vec![F::ONE; NUM_ARITH_COLUMNS - 1]];
+        let err = trace_to_csv(&trace, io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,100 @@
+//! Traces of a single operator, for benchmarking the constraints of one
+//! operator module in isolation, e.g. proving a trace of nothing but
+//! DIV to see what `div` costs.
+//!
+//! The inputs are random, in the form the witness generator passes
+//! them: immediates are sign-extended and LUI takes a 16-bit immediate.
+//! Shift amounts are nonzero modulo 32, so
+//! that no shift is proven as an `IS_SHIFT_ZERO` row and every
+//! operation takes the rows of its operator: a two-row operator such as
+//! DIV or SRA fills `2 * count` rows, which `estimated_rows` accounts
+//! for.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::utils::sign_extend_imm16;
+use super::{BinaryOperator, Operation};
+
+/// The seed of the inputs, so that a benchmark proves the same trace on
+/// every run.
+const UNIFORM_SEED: u64 = 0x6feb51b7ec230f25;
+
+/// Random inputs of `operator` as described in the module docs.
+fn uniform_inputs(rng: &mut ChaCha8Rng, operator: BinaryOperator) -> (u32, u32) {
+    let input0 = rng.gen::<u32>();
+    let input1 = rng.gen::<u32>();
+    match operator {
+        BinaryOperator::ADDI
+        | BinaryOperator::ADDIU
+        | BinaryOperator::SLTI
+        | BinaryOperator::SLTIU => (input0, sign_extend_imm16(input1 as u16)),
+        BinaryOperator::SLL | BinaryOperator::SRL | BinaryOperator::SRA => {
+            (input0, rng.gen_range(1..32))
+        }
+        BinaryOperator::SLLV | BinaryOperator::SRLV | BinaryOperator::SRAV => {
+            (input0, (input1 & !0x1f) | rng.gen_range(1..32))
+        }
+        BinaryOperator::LUI => (input0 & 0xffff, 0),
+        _ => (input0, input1),
+    }
+}
+
+/// `count` operations of `operator` on random inputs.
+pub(crate) fn uniform_operations(operator: BinaryOperator, count: usize) -> Vec<Operation> {
+    let mut rng = ChaCha8Rng::seed_from_u64(UNIFORM_SEED);
+    (0..count)
+        .map(|_| {
+            let (input0, input1) = uniform_inputs(&mut rng, operator);
+            Operation::binary(operator, input0, input1)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::*;
+    use crate::arithmetic::arithmetic_stark::ArithmeticStark;
+    use crate::arithmetic::columns::IS_SHIFT_ZERO;
+    use crate::arithmetic::estimated_rows;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn every_operation_takes_the_rows_of_its_operator() {
+        for &operator in BinaryOperator::all() {
+            let ops = uniform_operations(operator, 100);
+            assert_eq!(ops.len(), 100);
+            for op in &ops {
+                let (lv, nv) = op.to_rows::<F>();
+                assert_eq!(lv[operator.row_filter()], F::ONE, "{op:?}");
+                assert_eq!(lv[IS_SHIFT_ZERO], F::ZERO, "{op:?}");
+                assert_eq!(nv.is_some(), op.num_rows() == 2, "{op:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn two_row_operators_fill_twice_the_rows() {
+        let ops = uniform_operations(BinaryOperator::DIV, 1000);
+        assert_eq!(estimated_rows(&ops), 2000);
+
+        let trace = ArithmeticStark::<F, 2>::default().generate_trace(ops);
+        let filter = &trace[BinaryOperator::DIV.row_filter()].values;
+        assert_eq!(filter.iter().filter(|&&x| x == F::ONE).count(), 1000);
+        // The second row of each DIV directly follows its first.
+        assert!(filter[..2000].iter().step_by(2).all(|&x| x == F::ONE));
+        assert!(filter[1..2000].iter().step_by(2).all(|&x| x == F::ZERO));
+    }
+
+    #[test]
+    fn operations_are_deterministic() {
+        assert_eq!(
+            uniform_operations(BinaryOperator::SRAV, 10),
+            uniform_operations(BinaryOperator::SRAV, 10)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::{Add, AddAssign, Mul, Neg, Range, Shr, Sub, SubAssign};
+use core::ops::{Add, AddAssign, Mul, Neg, Range, Shr, Sub, SubAssign};
 
 use plonky2::field::extension::Extendable;
 use plonky2::field::types::{Field, PrimeField64};
@@ -321,11 +321,82 @@ pub(crate) fn read_value_i64_limbs<const N: usize, F: PrimeField64>(
     limbs.map(|c| c.to_canonical_u64() as i64)
 }
 
+/// Split `x` into the little-endian limbs of a register of the table;
+/// see `columns::N_LIMBS`.
+#[inline]
+pub fn pack_u32<F: Field>(x: u32) -> [F; N_LIMBS] {
+    const_assert!(LIMB_BITS == 16 && N_LIMBS == 2);
+
+    [x as u16, (x >> 16) as u16].map(F::from_canonical_u16)
+}
+
+/// The word held in a register of the table; see `columns::N_LIMBS`.
+/// Panics if a limb does not fit in `LIMB_BITS` bits.
+#[inline]
+pub fn unpack_u32<F: PrimeField64>(limbs: [F; N_LIMBS]) -> u32 {
+    let limbs = limbs.map(|limb| {
+        let limb = limb.to_canonical_u64();
+        assert!(limb >> LIMB_BITS == 0, "limb {limb:#x} is out of range");
+        limb as u32
+    });
+    limbs[0] | (limbs[1] << LIMB_BITS)
+}
+
 #[inline]
 pub fn u32_to_array<F: Field>(out: &mut [F], x: u32) {
-    const_assert!(LIMB_BITS == 16);
-    debug_assert!(out.len() == 2);
+    debug_assert!(out.len() == N_LIMBS);
+    out.copy_from_slice(&pack_u32(x));
+}
+
+/// Sign-extend the 16-bit immediate of an I-type instruction (ADDI,
+/// ADDIU, SLTI, SLTIU) to the 32-bit word the ALU operates on.
+#[inline]
+pub(crate) fn sign_extend_imm16(imm: u16) -> u32 {
+    imm as i16 as i32 as u32
+}
 
-    out[0] = F::from_canonical_u16(x as u16);
-    out[1] = F::from_canonical_u16((x >> 16) as u16);
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn pack_unpack_round_trips() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x6feb51b7ec230f25);
+        for x in [0, 1, 0xffff, 0x1_0000, u32::MAX]
+            .into_iter()
+            .chain((0..1000).map(|_| rng.gen()))
+        {
+            assert_eq!(unpack_u32(pack_u32::<F>(x)), x);
+        }
+    }
+
+    #[test]
+    fn limbs_are_little_endian_as_in_memory() {
+        // The memory table stores a word in one limb, and larger
+        // integers little-endian; the CPU lookup recombines the limbs
+        // of a register as x_0 + x_1 * 2^16.
+        let x = 0x1234_5678;
+        let limbs = pack_u32::<F>(x);
+        assert_eq!(limbs, [0x5678, 0x1234].map(F::from_canonical_u32));
+        assert_eq!(
+            limbs[0] + limbs[1] * F::from_canonical_u64(1 << LIMB_BITS),
+            F::from_canonical_u32(x)
+        );
+
+        let mut out = [F::ZERO; N_LIMBS];
+        u32_to_array(&mut out, x);
+        assert_eq!(out, limbs);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn unpack_rejects_wide_limbs() {
+        unpack_u32([F::from_canonical_u32(1 << 16), F::ZERO]);
+    }
 }
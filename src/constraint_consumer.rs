@@ -6,6 +6,17 @@ use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use serde::{Deserialize, Serialize};
+
+/// The rows a constraint applies to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ConstraintKind {
+    EveryRow,
+    /// Every row but the last; the constraint may read the next row.
+    Transition,
+    FirstRow,
+    LastRow,
+}
 
 pub struct ConstraintConsumer<P: PackedField> {
     /// Random values used to combine multiple constraints into one.
@@ -26,6 +37,10 @@ pub struct ConstraintConsumer<P: PackedField> {
     /// The evaluation of the Lagrange basis polynomial which is nonzero at the point associated
     /// with the last trace row, and zero at other points in the subgroup.
     lagrange_basis_last: P,
+
+    /// If set, every emitted constraint is also recorded here, before it
+    /// is scaled by alpha, to find which constraint fails.
+    residuals: Option<Vec<(P, ConstraintKind)>>,
 }
 
 impl<P: PackedField> ConstraintConsumer<P> {
@@ -41,6 +56,21 @@ impl<P: PackedField> ConstraintConsumer<P> {
             z_last,
             lagrange_basis_first,
             lagrange_basis_last,
+            residuals: None,
+        }
+    }
+
+    /// Like `new`, but also records each emitted constraint; see
+    /// `residuals`.
+    pub(crate) fn new_recording(
+        alphas: Vec<P::Scalar>,
+        z_last: P,
+        lagrange_basis_first: P,
+        lagrange_basis_last: P,
+    ) -> Self {
+        Self {
+            residuals: Some(vec![]),
+            ..Self::new(alphas, z_last, lagrange_basis_first, lagrange_basis_last)
         }
     }
 
@@ -48,29 +78,53 @@ impl<P: PackedField> ConstraintConsumer<P> {
         self.constraint_accs
     }
 
+    /// The constraints emitted so far, in order, if this consumer was
+    /// made with `new_recording`, and an empty vector otherwise.
+    pub(crate) fn residuals(self) -> Vec<P> {
+        self.recorded().into_iter().map(|(c, _)| c).collect()
+    }
+
+    /// Like `residuals`, together with the rows each constraint applies to.
+    pub(crate) fn recorded(self) -> Vec<(P, ConstraintKind)> {
+        self.residuals.unwrap_or_default()
+    }
+
     /// Add one constraint valid on all rows except the last.
     pub fn constraint_transition(&mut self, constraint: P) {
-        self.constraint(constraint * self.z_last);
+        self.emit(constraint * self.z_last, ConstraintKind::Transition);
     }
 
     /// Add one constraint on all rows.
     pub fn constraint(&mut self, constraint: P) {
-        for (&alpha, acc) in self.alphas.iter().zip(&mut self.constraint_accs) {
-            *acc *= alpha;
-            *acc += constraint;
-        }
+        self.emit(constraint, ConstraintKind::EveryRow);
     }
 
     /// Add one constraint, but first multiply it by a filter such that it will only apply to the
     /// first row of the trace.
     pub fn constraint_first_row(&mut self, constraint: P) {
-        self.constraint(constraint * self.lagrange_basis_first);
+        self.emit(
+            constraint * self.lagrange_basis_first,
+            ConstraintKind::FirstRow,
+        );
     }
 
     /// Add one constraint, but first multiply it by a filter such that it will only apply to the
     /// last row of the trace.
     pub fn constraint_last_row(&mut self, constraint: P) {
-        self.constraint(constraint * self.lagrange_basis_last);
+        self.emit(
+            constraint * self.lagrange_basis_last,
+            ConstraintKind::LastRow,
+        );
+    }
+
+    fn emit(&mut self, constraint: P, kind: ConstraintKind) {
+        for (&alpha, acc) in self.alphas.iter().zip(&mut self.constraint_accs) {
+            *acc *= alpha;
+            *acc += constraint;
+        }
+        if let Some(residuals) = &mut self.residuals {
+            residuals.push((constraint, kind));
+        }
     }
 }
 
@@ -144,6 +144,16 @@ impl<F: Field> Column<F> {
         Self::linear_combination(cs.into_iter().map(|c| *c.borrow()).zip(repeat(F::ONE)))
     }
 
+    /// The `(column, coefficient)` terms on the current row and on the
+    /// next row, and the constant.
+    pub(crate) fn terms(&self) -> (&[(usize, F)], &[(usize, F)], F) {
+        (
+            &self.linear_combination,
+            &self.next_row_linear_combination,
+            self.constant,
+        )
+    }
+
     pub fn eval<FE, P, const D: usize>(&self, v: &[P]) -> P
     where
         FE: FieldExtension<D, BaseField = F>,
@@ -263,6 +273,10 @@ impl<F: Field> TableWithColumns<F> {
             filter_column,
         }
     }
+
+    pub(crate) fn columns(&self) -> &[Column<F>] {
+        &self.columns
+    }
 }
 
 #[derive(Clone)]
@@ -7,6 +7,9 @@
 #![feature(decl_macro)]
 #![feature(generic_arg_infer)]
 #![allow(dead_code)]
+
+extern crate alloc;
+
 pub mod all_stark;
 pub mod arithmetic;
 pub mod config;
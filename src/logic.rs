@@ -333,7 +333,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for LogicStark<F,
 #[cfg(test)]
 mod tests {
 
-    use crate::logic::{LogicStark, Op, Operation};
+    use crate::logic::{columns, LogicStark, Op, Operation};
 
     use crate::stark_testing::{
         test_stark_check_constraints, test_stark_circuit_constraints, test_stark_low_degree,
@@ -341,6 +341,7 @@ mod tests {
 
     use anyhow::Result;
 
+    use plonky2::field::types::Field;
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 
     #[test]
@@ -414,4 +415,39 @@ mod tests {
             test_stark_check_constraints::<F, C, S, D>(stark, &vals[i], &vals[i + 1]);
         }
     }
+
+    #[test]
+    fn test_nor() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = LogicStark<F, D>;
+
+        let stark = S {
+            f: Default::default(),
+        };
+
+        let cases = [
+            (0, 0, 0xFFFFFFFF),
+            (0xF0F0F0F0, 0x0F0F0F0F, 0),
+            (0xFFFF0000, 0x000000FF, 0x0000FF00),
+        ];
+        let ops = cases
+            .iter()
+            .map(|&(a, b, expected)| {
+                let op = Operation::new(Op::Nor, a, b);
+                assert_eq!(op.result, expected);
+                op
+            })
+            .collect();
+        let vals = stark.generate_trace_rows(ops, 1 << 2);
+
+        for (row, &(_, _, expected)) in vals.iter().zip(cases.iter()) {
+            assert_eq!(row[columns::IS_NOR], F::ONE);
+            assert_eq!(row[columns::RESULT.start], F::from_canonical_u32(expected));
+        }
+        for i in 0..(vals.len() - 1) {
+            test_stark_check_constraints::<F, C, S, D>(stark, &vals[i], &vals[i + 1]);
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use super::util::*;
+use crate::arithmetic::utils::sign_extend_imm16;
 use crate::cpu::columns::CpuColumnsView;
 use crate::cpu::kernel::assembler::Kernel;
 use crate::generation::state::GenerationState;
@@ -307,9 +308,9 @@ pub(crate) fn generate_binary_arithmetic_imm_op<F: Field>(
     mut row: CpuColumnsView<F>,
 ) -> Result<(), ProgramError> {
     let (in0, log_in0) = reg_read_with_log(rs, 0, state, &mut row)?;
-    let in1 = sign_extend::<16>(imm);
+    let in1 = sign_extend_imm16(imm as u16);
     let log_in1 = reg_write_with_log(rt, 1, in1 as usize, state, &mut row)?;
-    let operation = arithmetic::Operation::binary(operator, in0 as u32, in1);
+    let operation = arithmetic::Operation::binary_imm(operator, in0 as u32, imm as u16);
 
     let out = operation.result().0;
     let log_out0 = reg_write_with_log(rt, 2, out as usize, state, &mut row)?;
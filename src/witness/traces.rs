@@ -7,7 +7,6 @@ use plonky2::util::timing::TimingTree;
 use std::mem::size_of;
 
 use crate::all_stark::{AllStark, NUM_TABLES};
-use crate::arithmetic::{BinaryOperator, Operation};
 //use crate::byte_packing::byte_packing_stark::BytePackingOp;
 use crate::config::StarkConfig;
 use crate::cpu::columns::CpuColumnsView;
@@ -59,16 +58,7 @@ impl<T: Copy> Traces<T> {
     //  Uses a `TraceCheckPoint` as return object for convenience.
     pub fn get_lengths(&self) -> TraceCheckpoint {
         TraceCheckpoint {
-            arithmetic_len: self
-                .arithmetic_ops
-                .iter()
-                .map(|op| match op {
-                    Operation::BinaryOperation { operator, .. } => match operator {
-                        BinaryOperator::DIV => 2,
-                        _ => 1,
-                    },
-                })
-                .sum(),
+            arithmetic_len: arithmetic::estimated_rows(&self.arithmetic_ops),
             // byte_packing_len: self.byte_packing_ops.iter().map(|op| op.bytes.len()).sum(),
             cpu_len: self.cpu.len(),
             keccak_len: self.keccak_inputs.len() * keccak_stark::NUM_ROUNDS,
@@ -84,6 +84,8 @@ fn decode(registers: RegistersState, insn: u32) -> Result<Operation, ProgramErro
             rt,
             rd,
         )), // SUBU: rd = rs-rt
+        // NOP, SSNOP, EHB and PAUSE are sll $0, $0, sa
+        (0b000000, 0b000000, _) if rs == 0 && rt == 0 && rd == 0 => Ok(Operation::Nop),
         (0b000000, 0b000000, _) => Ok(Operation::BinaryArithmetic(
             arithmetic::BinaryOperator::SLL,
             sa,
@@ -174,7 +176,7 @@ fn decode(registers: RegistersState, insn: u32) -> Result<Operation, ProgramErro
             0,
             32,
         )), // MTLO: lo = rs
-        (0b000000, 0b001111, _) => Ok(Operation::Nop),                                 // SYNC
+        (0b000000, 0b001111, _) => Ok(Operation::Nop), // SYNC
         (0b011100, 0b100000, _) => Ok(Operation::Count(false, rs, rd)), // CLZ: rd = count_leading_zeros(rs)
         (0b011100, 0b100001, _) => Ok(Operation::Count(true, rs, rd)), // CLO: rd = count_leading_ones(rs)
         (0x00, 0x08, _) => Ok(Operation::Jump(0u8, rs)),               // JR
@@ -563,3 +565,204 @@ pub(crate) fn transition<F: Field>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+    use std::collections::HashMap;
+
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+
+    use super::*;
+    use crate::arithmetic::arithmetic_stark::ctl_arithmetic_rows;
+    use crate::cpu::columns::NUM_CPU_COLUMNS;
+    use crate::cpu::cpu_stark::{ctl_arithmetic_base_rows, ctl_arithmetic_imm_base_rows};
+    use crate::cpu::kernel::elf::Program;
+    use crate::cross_table_lookup::TableWithColumns;
+    use crate::witness::memory::MemoryState;
+    use crate::witness::traces::Traces;
+
+    type F = GoldilocksField;
+
+    fn r_type(opcode: u32, rs: u8, rt: u8, rd: u8, func: u32) -> u32 {
+        (opcode << 26) | ((rs as u32) << 21) | ((rt as u32) << 16) | ((rd as u32) << 11) | func
+    }
+
+    /// Run `program`, placed at address 0, from the registers `gprs`.
+    /// The bootstrap and exit kernels are skipped, so the CPU trace has
+    /// one row per instruction.
+    fn run(program: &[u32], gprs: [usize; 32]) -> GenerationState<F> {
+        let kernel = Kernel {
+            program: Program {
+                gprs,
+                ..Default::default()
+            },
+            ordered_labels: vec![],
+            global_labels: HashMap::new(),
+            blockpath: String::new(),
+            steps: program.len(),
+        };
+        let mut state = GenerationState {
+            registers: RegistersState::new(&kernel),
+            memory: MemoryState::default(),
+            traces: Traces::default(),
+            step: kernel.steps,
+        };
+        for (i, &insn) in program.iter().enumerate() {
+            let address = MemoryAddress::new(0, Segment::Code, 4 * i);
+            state.memory.set(address, insn.to_be());
+        }
+        for _ in program {
+            transition(&mut state, &kernel).unwrap();
+        }
+        state
+    }
+
+    fn eval(table: &TableWithColumns<F>, row: &[F]) -> (F, Vec<F>) {
+        let filter = table
+            .filter_column
+            .as_ref()
+            .map_or(F::ONE, |f| f.eval::<F, F, 1>(row));
+        let columns = table.columns().iter().map(|c| c.eval::<F, F, 1>(row));
+        (filter, columns.collect())
+    }
+
+    /// The clocks of the CPU rows in the arithmetic lookup whose opcode,
+    /// inputs or written result differ from those of the arithmetic
+    /// operation of the same cycle. The CTL only checks that the two
+    /// sides agree up to order; here both are taken in program order,
+    /// so that a result written to the wrong cycle is caught as well.
+    fn mismatched_cycles(state: &GenerationState<F>) -> Vec<u64> {
+        let cpu_tables = [ctl_arithmetic_base_rows(), ctl_arithmetic_imm_base_rows()];
+        let cpu_rows = state.traces.cpu.iter().filter_map(|row| {
+            let values: &[F; NUM_CPU_COLUMNS] = row.borrow();
+            cpu_tables
+                .iter()
+                .map(|table| eval(table, values))
+                .find(|(filter, _)| *filter == F::ONE)
+                .map(|(_, columns)| (row.clock.to_canonical_u64(), columns))
+        });
+
+        let arithmetic_table = ctl_arithmetic_rows();
+        let arithmetic_rows = state.traces.arithmetic_ops.iter().map(|op| {
+            let (lv, _) = op.to_rows::<F>();
+            let (filter, columns) = eval(&arithmetic_table, &lv);
+            assert_eq!(filter, F::ONE, "{op:?} is not in the CPU lookup");
+            columns
+        });
+
+        let cpu_rows = cpu_rows.collect::<Vec<_>>();
+        let arithmetic_rows = arithmetic_rows.collect::<Vec<_>>();
+        assert_eq!(cpu_rows.len(), arithmetic_rows.len());
+        cpu_rows
+            .into_iter()
+            .zip(arithmetic_rows)
+            .filter(|((_, cpu), arithmetic)| cpu != arithmetic)
+            .map(|((clock, _), _)| clock)
+            .collect()
+    }
+
+    /// ADD, SUB, MUL and DIV, each reading the result of the one before.
+    fn add_sub_mul_div() -> (Vec<u32>, [usize; 32]) {
+        let program = vec![
+            r_type(0b000000, 1, 2, 3, 0b100000), // ADD $3, $1, $2
+            r_type(0b000000, 3, 1, 4, 0b100010), // SUB $4, $3, $1
+            r_type(0b011100, 3, 4, 5, 0b000010), // MUL $5, $3, $4
+            r_type(0b000000, 5, 2, 0, 0b011010), // DIV $5, $2
+        ];
+        let mut gprs = [0; 32];
+        gprs[1] = 0x1234_5678;
+        gprs[2] = 0xffff_fff9;
+        (program, gprs)
+    }
+
+    #[test]
+    fn arithmetic_results_match_register_writes() {
+        let (program, gprs) = add_sub_mul_div();
+        let state = run(&program, gprs);
+
+        let r3 = 0x1234_5678u32.wrapping_add(0xffff_fff9);
+        let r4 = r3.wrapping_sub(0x1234_5678);
+        let r5 = r3.wrapping_mul(r4);
+        let registers = state.registers;
+        assert_eq!(registers.gprs[3..6], [r3, r4, r5].map(|r| r as usize));
+        assert_eq!(registers.lo, ((r5 as i32) / -7) as u32 as usize);
+        assert_eq!(registers.hi, ((r5 as i32) % -7) as u32 as usize);
+
+        assert_eq!(state.traces.arithmetic_ops.len(), program.len());
+        assert_eq!(mismatched_cycles(&state), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn variable_shifts_send_the_unmasked_amount() {
+        let program = vec![
+            r_type(0b000000, 1, 2, 3, 0b000100), // SLLV $3, $2, $1
+            r_type(0b000000, 1, 2, 4, 0b000110), // SRLV $4, $2, $1
+            r_type(0b000000, 1, 2, 5, 0b000111), // SRAV $5, $2, $1
+        ];
+        let x = 0x8765_4321u32;
+        let mut gprs = [0; 32];
+        // Only the low 5 bits are used, so this shifts by 1; the CPU
+        // still sends 33 to the arithmetic table.
+        gprs[1] = 33;
+        gprs[2] = x as usize;
+        let state = run(&program, gprs);
+
+        let expected = [x << 1, x >> 1, ((x as i32) >> 1) as u32];
+        assert_eq!(state.registers.gprs[3..6], expected.map(|r| r as usize));
+        assert_eq!(state.traces.arithmetic_ops.len(), program.len());
+        assert_eq!(mismatched_cycles(&state), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn variable_shifts_by_multiples_of_32_match() {
+        let program = vec![
+            r_type(0b000000, 1, 2, 3, 0b000100), // SLLV $3, $2, $1
+            r_type(0b000000, 1, 2, 4, 0b000110), // SRLV $4, $2, $1
+            r_type(0b000000, 1, 2, 5, 0b000111), // SRAV $5, $2, $1
+        ];
+        let x = 0x8765_4321;
+        // Shifts by 0 take the one-row shift-by-zero path, shifts by
+        // other multiples of 32 the rows of their shift.
+        for rs in [0, 32, 64, 0xffff_ffe0] {
+            let mut gprs = [0; 32];
+            gprs[1] = rs;
+            gprs[2] = x;
+            let state = run(&program, gprs);
+
+            assert_eq!(state.registers.gprs[3..6], [x; 3], "rs = {rs:#x}");
+            assert_eq!(mismatched_cycles(&state), Vec::<u64>::new(), "rs = {rs:#x}");
+        }
+    }
+
+    #[test]
+    fn nops_have_no_arithmetic_rows() {
+        let nop = 0;
+        let sync = r_type(0b000000, 0, 0, 0, 0b001111);
+        let mut program = vec![nop; 1000];
+        program[500] = sync;
+        let state = run(&program, [0; 32]);
+
+        assert!(state.traces.arithmetic_ops.is_empty());
+        assert_eq!(state.traces.cpu.len(), program.len());
+        assert!(state.traces.cpu.iter().all(|row| row.op.nop == F::ONE));
+        assert_eq!(mismatched_cycles(&state), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn wrong_register_writes_are_caught() {
+        let (program, gprs) = add_sub_mul_div();
+        let state = run(&program, gprs);
+
+        // A wrong value written by SUB.
+        let mut bad = state.clone();
+        bad.traces.cpu[1].mem_channels[2].value[0] += F::ONE;
+        assert_eq!(mismatched_cycles(&bad), vec![1]);
+
+        // A stale value: MUL writes the result of SUB.
+        let mut bad = state;
+        bad.traces.cpu[2].mem_channels[2].value[0] = bad.traces.cpu[1].mem_channels[2].value[0];
+        assert_eq!(mismatched_cycles(&bad), vec![2]);
+    }
+}